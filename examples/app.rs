@@ -0,0 +1,64 @@
+use {
+    egui_sfml::{run_app, App, EventResult, Frame, SfEgui, WindowOptions},
+    sfml::{
+        graphics::{CircleShape, Color, RenderTarget, RenderWindow, Shape, Transformable},
+        window::{mouse, Event},
+    },
+};
+
+struct MyApp {
+    pos: (f32, f32),
+    radius: f32,
+    color: [f32; 3],
+}
+
+impl App for MyApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut Frame) {
+        egui::Window::new("Circle").show(ctx, |ui| {
+            ui.add(egui::Slider::new(&mut self.radius, 10.0..=200.0).text("Radius"));
+            ui.color_edit_button_rgb(&mut self.color);
+            if ui.button("Quit").clicked() {
+                frame.close();
+            }
+        });
+    }
+    fn on_event(&mut self, event: &Event, result: EventResult) {
+        // Clicks that egui doesn't care about move the circle
+        if let Event::MouseButtonPressed {
+            button: mouse::Button::Left,
+            x,
+            y,
+        } = *event
+        {
+            if !result.is_consumed() {
+                self.pos = (x as f32, y as f32);
+            }
+        }
+    }
+    fn draw_before_ui(&mut self, rw: &mut RenderWindow, _sf_egui: &mut SfEgui) {
+        let mut circle = CircleShape::new(self.radius, 64);
+        circle.set_origin((self.radius, self.radius));
+        circle.set_position(self.pos);
+        let [r, g, b] = self.color.map(|c| (c * 255.) as u8);
+        circle.set_fill_color(Color::rgb(r, g, b));
+        rw.draw(&circle);
+    }
+    fn on_exit(&mut self) {
+        println!("Bye!");
+    }
+}
+
+fn main() {
+    let app = MyApp {
+        pos: (400., 300.),
+        radius: 100.,
+        color: [0.4, 0.6, 0.2],
+    };
+    let options = WindowOptions {
+        title: "egui-sfml App".into(),
+        ..Default::default()
+    };
+    if let Err(e) = run_app(options, app) {
+        eprintln!("{e}");
+    }
+}
@@ -23,9 +23,9 @@ fn main() {
     let mut messages = Vec::new();
 
     while rw.is_open() {
-        while let Some(event) = rw.poll_event() {
-            // Step 2: Collect events from the event loop
-            sfegui.add_event(&event);
+        // Step 2: Collect events from the event loop.
+        // This waits until there is new input, or egui wants to repaint.
+        for event in sfegui.wait_events(&mut rw) {
             if matches!(event, Event::Closed) {
                 rw.close();
             }
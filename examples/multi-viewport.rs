@@ -0,0 +1,90 @@
+use {
+    egui_sfml::SfEgui,
+    sfml::{
+        graphics::{Color, RenderTarget, RenderWindow},
+        window::{ContextSettings, Event, Style},
+    },
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+fn main() {
+    let mut rw = RenderWindow::new(
+        (800, 600),
+        "Multi-viewport",
+        Style::CLOSE,
+        &ContextSettings::default(),
+    )
+    .unwrap();
+    rw.set_vertical_sync_enabled(true);
+    let mut sfegui = SfEgui::new(&rw);
+    sfegui.set_multi_viewport(true);
+
+    let show_deferred = Arc::new(AtomicBool::new(false));
+    let mut show_immediate = false;
+    let mut counter = 0;
+
+    while rw.is_open() {
+        while let Some(event) = rw.poll_event() {
+            sfegui.add_event(&event);
+            if matches!(event, Event::Closed) {
+                rw.close();
+            }
+        }
+        let di = sfegui
+            .run(&mut rw, |_rw, ctx| {
+                egui::CentralPanel::default().show(ctx, |ui| {
+                    let mut deferred = show_deferred.load(Ordering::Relaxed);
+                    if ui
+                        .checkbox(&mut deferred, "Show deferred viewport")
+                        .changed()
+                    {
+                        show_deferred.store(deferred, Ordering::Relaxed);
+                    }
+                    ui.checkbox(&mut show_immediate, "Show immediate viewport");
+                    ui.label(format!("Counter: {counter}"));
+                });
+                if show_deferred.load(Ordering::Relaxed) {
+                    let show_deferred = show_deferred.clone();
+                    ctx.show_viewport_deferred(
+                        egui::ViewportId::from_hash_of("deferred"),
+                        egui::ViewportBuilder::default()
+                            .with_title("Deferred viewport")
+                            .with_inner_size([300., 200.]),
+                        move |ctx, _class| {
+                            egui::CentralPanel::default().show(ctx, |ui| {
+                                ui.label("Hello from a deferred viewport!");
+                            });
+                            if ctx.input(|inp| inp.viewport().close_requested()) {
+                                show_deferred.store(false, Ordering::Relaxed);
+                            }
+                        },
+                    );
+                }
+                if show_immediate {
+                    ctx.show_viewport_immediate(
+                        egui::ViewportId::from_hash_of("immediate"),
+                        egui::ViewportBuilder::default()
+                            .with_title("Immediate viewport")
+                            .with_inner_size([300., 200.]),
+                        |ctx, _class| {
+                            egui::CentralPanel::default().show(ctx, |ui| {
+                                if ui.button("Increment").clicked() {
+                                    counter += 1;
+                                }
+                            });
+                            if ctx.input(|inp| inp.viewport().close_requested()) {
+                                show_immediate = false;
+                            }
+                        },
+                    );
+                }
+            })
+            .unwrap();
+        rw.clear(Color::rgb(95, 106, 62));
+        sfegui.draw(di, &mut rw, None);
+        rw.display();
+    }
+}
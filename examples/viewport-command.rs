@@ -13,6 +13,10 @@ struct UiState {
     title: String,
     invisible_set_instant: Option<Instant>,
     focus_req_instant: Option<Instant>,
+    fullscreen: bool,
+    resizable: bool,
+    decorations: bool,
+    screenshot_size: Option<[usize; 2]>,
 }
 
 fn main() {
@@ -25,7 +29,12 @@ fn main() {
     .unwrap();
     rw.set_vertical_sync_enabled(true);
     let mut sf_egui = SfEgui::new(&rw);
-    let mut ui_state = UiState::default();
+    sf_egui.set_window_attributes("ViewportCommand test", Style::DEFAULT);
+    let mut ui_state = UiState {
+        resizable: true,
+        decorations: true,
+        ..Default::default()
+    };
     let mut view = View::new().unwrap();
 
     while rw.is_open() {
@@ -77,5 +86,40 @@ fn ui(ctx: &egui::Context, state: &mut UiState) {
                 state.focus_req_instant = None;
             }
         }
+        ui.separator();
+        if ui.checkbox(&mut state.fullscreen, "Fullscreen").changed() {
+            ctx.send_viewport_cmd(ViewportCommand::Fullscreen(state.fullscreen));
+        }
+        if ui.checkbox(&mut state.resizable, "Resizable").changed() {
+            ctx.send_viewport_cmd(ViewportCommand::Resizable(state.resizable));
+        }
+        if ui.checkbox(&mut state.decorations, "Decorations").changed() {
+            ctx.send_viewport_cmd(ViewportCommand::Decorations(state.decorations));
+        }
+        ui.horizontal(|ui| {
+            if ui.button("Size 800x600").clicked() {
+                ctx.send_viewport_cmd(ViewportCommand::InnerSize(egui::vec2(800., 600.)));
+            }
+            if ui.button("Move to 100,100").clicked() {
+                ctx.send_viewport_cmd(ViewportCommand::OuterPosition(egui::pos2(100., 100.)));
+            }
+            if ui.button("Min size 400x300").clicked() {
+                ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(egui::vec2(400., 300.)));
+            }
+        });
+        ui.separator();
+        if ui.button("Take screenshot").clicked() {
+            ctx.send_viewport_cmd(ViewportCommand::Screenshot(Default::default()));
+        }
+        ctx.input(|inp| {
+            for ev in &inp.raw.events {
+                if let egui::Event::Screenshot { image, .. } = ev {
+                    state.screenshot_size = Some(image.size);
+                }
+            }
+        });
+        if let Some([w, h]) = state.screenshot_size {
+            ui.label(format!("Got a {w}x{h} screenshot"));
+        }
     });
 }
@@ -0,0 +1,275 @@
+use {
+    crate::{fonts::FontSet, translate::CommandKey, Clipboard, SfEgui, Window},
+    egui::{Context, Style, ThemePreference},
+    sfml::window::Style as WindowStyle,
+    std::sync::Arc,
+};
+
+/// Configures a new [`SfEgui`].
+///
+/// ```no_run
+/// # fn f(window: &sfml::graphics::RenderWindow) -> Result<(), egui_sfml::BuildError> {
+/// use egui_sfml::{egui::ThemePreference, SfEgui};
+///
+/// let sf_egui = SfEgui::builder()
+///     .pixels_per_point(1.5)
+///     .theme(ThemePreference::Dark)
+///     .multi_viewport(true)
+///     .build(window)?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Everything that isn't set keeps the default of [`SfEgui::new`].
+pub struct SfEguiBuilder {
+    context: Option<Context>,
+    pixels_per_point: Option<f32>,
+    theme: Option<ThemePreference>,
+    style: Option<Arc<Style>>,
+    max_texture_side: Option<usize>,
+    fonts: Option<FontSet>,
+    image_loaders: bool,
+    clipboard: Option<Box<dyn Clipboard>>,
+    window_attributes: Option<(String, WindowStyle)>,
+    manage_cursor: bool,
+    continuous_repaint: bool,
+    repaint_callback: Option<bool>,
+    preserve_gl_states: bool,
+    multi_viewport: bool,
+    resync_modifiers: bool,
+    command_key: CommandKey,
+}
+
+impl std::fmt::Debug for SfEguiBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SfEguiBuilder")
+            .field("pixels_per_point", &self.pixels_per_point)
+            .field("theme", &self.theme)
+            .field("max_texture_side", &self.max_texture_side)
+            .field("image_loaders", &self.image_loaders)
+            .field("window_attributes", &self.window_attributes)
+            .field("manage_cursor", &self.manage_cursor)
+            .field("continuous_repaint", &self.continuous_repaint)
+            .field("repaint_callback", &self.repaint_callback)
+            .field("preserve_gl_states", &self.preserve_gl_states)
+            .field("multi_viewport", &self.multi_viewport)
+            .field("resync_modifiers", &self.resync_modifiers)
+            .field("command_key", &self.command_key)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for SfEguiBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SfEguiBuilder {
+    /// A builder with the defaults of [`SfEgui::new`]
+    pub fn new() -> Self {
+        Self {
+            context: None,
+            pixels_per_point: None,
+            theme: None,
+            style: None,
+            max_texture_side: None,
+            fonts: None,
+            image_loaders: false,
+            clipboard: None,
+            window_attributes: None,
+            manage_cursor: true,
+            continuous_repaint: false,
+            repaint_callback: None,
+            preserve_gl_states: false,
+            multi_viewport: false,
+            resync_modifiers: false,
+            command_key: CommandKey::native(),
+        }
+    }
+    /// Use an existing egui context, for example to share it with another integration.
+    ///
+    /// The settings of the context are only changed where this builder asks for it,
+    /// and its request repaint callback is kept, see [`Self::repaint_callback`].
+    pub fn context(mut self, ctx: Context) -> Self {
+        self.context = Some(ctx);
+        self
+    }
+    /// The scale of the ui at a zoom factor of 1.
+    ///
+    /// SFML doesn't report the scale factor of the monitor, so this is reported to egui as
+    /// the native pixels per point of the window. The user can still zoom with Ctrl +/-,
+    /// and Ctrl+0 goes back to this scale.
+    pub fn pixels_per_point(mut self, pixels_per_point: f32) -> Self {
+        self.pixels_per_point = Some(pixels_per_point);
+        self
+    }
+    /// Whether to use the dark or the light theme, see [`Context::set_theme`]
+    pub fn theme(mut self, theme: impl Into<ThemePreference>) -> Self {
+        self.theme = Some(theme.into());
+        self
+    }
+    /// The style of the current theme, see [`Context::set_style`]
+    pub fn style(mut self, style: impl Into<Arc<Style>>) -> Self {
+        self.style = Some(style.into());
+        self
+    }
+    /// Limit the size of the textures egui creates, in pixels per side.
+    ///
+    /// Defaults to the limit of the GPU. Can't be larger than that.
+    pub fn max_texture_side(mut self, max_texture_side: usize) -> Self {
+        self.max_texture_side = Some(max_texture_side);
+        self
+    }
+    /// Install `fonts`, see [`SfEgui::install_fonts`]
+    pub fn fonts(mut self, fonts: FontSet) -> Self {
+        self.fonts = Some(fonts);
+        self
+    }
+    /// Install the SFML image loaders, see [`SfEgui::install_image_loaders`]
+    pub fn image_loaders(mut self, enabled: bool) -> Self {
+        self.image_loaders = enabled;
+        self
+    }
+    /// See [`SfEgui::set_clipboard`]
+    pub fn clipboard(mut self, clipboard: impl Clipboard + 'static) -> Self {
+        self.clipboard = Some(Box::new(clipboard));
+        self
+    }
+    /// See [`SfEgui::set_window_attributes`]
+    pub fn window_attributes(mut self, title: impl Into<String>, style: WindowStyle) -> Self {
+        self.window_attributes = Some((title.into(), style));
+        self
+    }
+    /// See [`SfEgui::set_manage_cursor`]
+    pub fn manage_cursor(mut self, enabled: bool) -> Self {
+        self.manage_cursor = enabled;
+        self
+    }
+    /// See [`SfEgui::set_continuous_repaint`]
+    pub fn continuous_repaint(mut self, enabled: bool) -> Self {
+        self.continuous_repaint = enabled;
+        self
+    }
+    /// Install a request repaint callback on the context, so repaints requested from
+    /// other threads end [`SfEgui::wait_events`].
+    ///
+    /// A context only has one such callback, so this defaults to `false` when an existing
+    /// context is given with [`Self::context`], and to `true` otherwise.
+    pub fn repaint_callback(mut self, enabled: bool) -> Self {
+        self.repaint_callback = Some(enabled);
+        self
+    }
+    /// See [`SfEgui::set_preserve_gl_states`]
+    pub fn preserve_gl_states(mut self, enabled: bool) -> Self {
+        self.preserve_gl_states = enabled;
+        self
+    }
+    /// See [`SfEgui::set_multi_viewport`]
+    pub fn multi_viewport(mut self, enabled: bool) -> Self {
+        self.multi_viewport = enabled;
+        self
+    }
+    /// See [`SfEgui::set_resync_modifiers_on_focus`]
+    pub fn resync_modifiers_on_focus(mut self, enabled: bool) -> Self {
+        self.resync_modifiers = enabled;
+        self
+    }
+    /// See [`SfEgui::set_command_key`]
+    pub fn command_key(mut self, key: CommandKey) -> Self {
+        self.command_key = key;
+        self
+    }
+    /// Check the configuration, and create an `SfEgui` for `window`
+    pub fn build(self, window: &dyn Window) -> Result<SfEgui, BuildError> {
+        if let Some(ppp) = self.pixels_per_point {
+            if !(ppp.is_finite() && ppp > 0.0) {
+                return Err(BuildError::InvalidPixelsPerPoint(ppp));
+            }
+        }
+        if let Some(requested) = self.max_texture_side {
+            let supported = window.max_texture_side();
+            if requested == 0 || requested > supported {
+                return Err(BuildError::InvalidMaxTextureSide {
+                    requested,
+                    supported,
+                });
+            }
+        }
+        let repaint_callback = self.repaint_callback.unwrap_or(self.context.is_none());
+        let mut sf_egui = SfEgui::with_context(self.context.unwrap_or_default(), window);
+        let ctx = sf_egui.ctx.clone();
+        if repaint_callback {
+            sf_egui.install_repaint_callback();
+        }
+        if let Some(ppp) = self.pixels_per_point {
+            let state = &mut sf_egui.window_state;
+            state.info.native_pixels_per_point = Some(ppp);
+            let next_ppp = crate::next_pixels_per_point(&ctx, &state.info);
+            state.update_info(window, next_ppp);
+        }
+        if let Some(theme) = self.theme {
+            ctx.set_theme(theme);
+        }
+        if let Some(style) = self.style {
+            ctx.set_style(style);
+        }
+        if let Some(max_texture_side) = self.max_texture_side {
+            sf_egui.raw_input.max_texture_side = Some(max_texture_side);
+        }
+        if let Some(fonts) = self.fonts {
+            sf_egui.install_fonts(fonts);
+        }
+        if self.image_loaders {
+            sf_egui.install_image_loaders();
+        }
+        if let Some(clipboard) = self.clipboard {
+            *sf_egui.shared.clipboard.borrow_mut() = clipboard;
+        }
+        if let Some((title, style)) = self.window_attributes {
+            sf_egui.set_window_attributes(&title, style);
+        }
+        sf_egui.set_manage_cursor(self.manage_cursor);
+        sf_egui.set_continuous_repaint(self.continuous_repaint);
+        sf_egui.set_preserve_gl_states(self.preserve_gl_states);
+        if self.multi_viewport {
+            sf_egui.set_multi_viewport(true);
+        }
+        sf_egui.set_resync_modifiers_on_focus(self.resync_modifiers);
+        sf_egui.set_command_key(self.command_key);
+        Ok(sf_egui)
+    }
+}
+
+/// Error when an [`SfEguiBuilder`] is configured wrong
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum BuildError {
+    /// The pixels per point are not a positive number
+    InvalidPixelsPerPoint(f32),
+    /// The max texture side is zero, or larger than the GPU supports
+    InvalidMaxTextureSide {
+        /// The max texture side that was set
+        requested: usize,
+        /// The largest texture side the GPU supports
+        supported: usize,
+    },
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidPixelsPerPoint(ppp) => {
+                f.write_fmt(format_args!("Invalid pixels per point: {ppp}"))
+            }
+            BuildError::InvalidMaxTextureSide {
+                requested,
+                supported,
+            } => f.write_fmt(format_args!(
+                "Invalid max texture side {requested}, must be between 1 and {supported}"
+            )),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
@@ -0,0 +1,45 @@
+use sfml::window::clipboard;
+
+/// A clipboard backend used by [`SfEgui`](crate::SfEgui) for copy and paste.
+///
+/// Implement this to route clipboard access through your own clipboard manager,
+/// then install it with [`SfEgui::set_clipboard`](crate::SfEgui::set_clipboard).
+pub trait Clipboard {
+    /// Returns the current text contents of the clipboard.
+    fn get(&mut self) -> String;
+    /// Replaces the contents of the clipboard with `text`.
+    fn set(&mut self, text: &str);
+}
+
+/// The system clipboard, as exposed by SFML.
+///
+/// This is the default clipboard of [`SfEgui`](crate::SfEgui).
+#[derive(Default, Debug, Clone, Copy)]
+pub struct SfmlClipboard;
+
+impl Clipboard for SfmlClipboard {
+    fn get(&mut self) -> String {
+        clipboard::get_string()
+    }
+    fn set(&mut self, text: &str) {
+        clipboard::set_string(text);
+    }
+}
+
+/// A clipboard that only lives in memory.
+///
+/// Useful for tests, or when you don't want egui to touch the system clipboard.
+#[derive(Default, Debug, Clone)]
+pub struct MemoryClipboard {
+    /// The current contents of the clipboard
+    pub contents: String,
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> String {
+        self.contents.clone()
+    }
+    fn set(&mut self, text: &str) {
+        text.clone_into(&mut self.contents);
+    }
+}
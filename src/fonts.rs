@@ -0,0 +1,307 @@
+//! Building egui fonts from font files
+//!
+//! ```no_run
+//! # fn f(sf_egui: &egui_sfml::SfEgui) -> Result<(), egui_sfml::fonts::FontError> {
+//! use egui_sfml::{egui::FontFamily, fonts::FontSet};
+//!
+//! let fonts = FontSet::new()
+//!     .with_file("brand", "assets/brand.otf")?
+//!     .with_file("cjk", "assets/NotoSansCJK-Regular.ttc")?
+//!     .primary(FontFamily::Proportional, "brand")
+//!     .fallback(FontFamily::Proportional, "cjk");
+//! sf_egui.install_fonts(fonts);
+//! # Ok(())
+//! # }
+//! ```
+
+use {
+    crate::SfEgui,
+    egui::{FontData, FontDefinitions, FontFamily},
+    sfml::graphics::Font,
+    std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    },
+};
+
+/// Builds [`FontDefinitions`] from font files, with fallback chains per [`FontFamily`].
+///
+/// egui tries the fonts of a family in order, so the first one is the primary font, and the
+/// rest are fallbacks for glyphs it doesn't have.
+#[derive(Clone)]
+pub struct FontSet {
+    definitions: FontDefinitions,
+}
+
+impl Default for FontSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FontSet {
+    /// Start from egui's default fonts
+    pub fn new() -> Self {
+        Self {
+            definitions: FontDefinitions::default(),
+        }
+    }
+    /// Start without any fonts
+    pub fn empty() -> Self {
+        Self {
+            definitions: FontDefinitions::empty(),
+        }
+    }
+    /// Load the TTF or OTF file at `path` as the font `name`.
+    ///
+    /// The font isn't used until it's added to a family, with [`Self::primary`] or
+    /// [`Self::fallback`].
+    pub fn with_file(
+        self,
+        name: impl Into<String>,
+        path: impl AsRef<Path>,
+    ) -> Result<Self, FontError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|e| FontError::Io(path.to_owned(), e))?;
+        if !is_loadable(&data) {
+            return Err(FontError::Invalid(path.to_owned()));
+        }
+        Ok(self.with_data(name, FontData::from_owned(data)))
+    }
+    /// Add `data` as the font `name`, replacing any font with the same name
+    pub fn with_data(mut self, name: impl Into<String>, data: FontData) -> Self {
+        self.definitions
+            .font_data
+            .insert(name.into(), Arc::new(data));
+        self
+    }
+    /// Find the system font `query` with [`find_system_font`], and load it as the font `name`
+    #[cfg(target_os = "linux")]
+    pub fn with_system_font(self, name: impl Into<String>, query: &str) -> Result<Self, FontError> {
+        let path = find_system_font(query).ok_or_else(|| FontError::NotFound(query.to_owned()))?;
+        self.with_file(name, path)
+    }
+    /// Make the font `name` the first choice for `family`
+    pub fn primary(mut self, family: FontFamily, name: &str) -> Self {
+        let names = self.family_mut(family);
+        names.retain(|n| n != name);
+        names.insert(0, name.to_owned());
+        self
+    }
+    /// Try the font `name` for `family` after all fonts added before it
+    pub fn fallback(mut self, family: FontFamily, name: &str) -> Self {
+        let names = self.family_mut(family);
+        names.retain(|n| n != name);
+        names.push(name.to_owned());
+        self
+    }
+    /// Replace the fallback chain of `family` with `names`, in order of priority
+    pub fn chain(mut self, family: FontFamily, names: &[&str]) -> Self {
+        *self.family_mut(family) = names.iter().map(|&n| n.to_owned()).collect();
+        self
+    }
+    /// Get the font definitions to give to [`Context::set_fonts`](egui::Context::set_fonts).
+    ///
+    /// Fonts in the chains that were never loaded are left out, since egui panics on them.
+    pub fn build(mut self) -> FontDefinitions {
+        let loaded = &self.definitions.font_data;
+        for (family, names) in &mut self.definitions.families {
+            names.retain(|name| {
+                let known = loaded.contains_key(name);
+                if !known {
+                    eprintln!("egui_sfml: Font {name:?} in {family:?} chain was never loaded");
+                }
+                known
+            });
+        }
+        self.definitions
+    }
+    fn family_mut(&mut self, family: FontFamily) -> &mut Vec<String> {
+        self.definitions.families.entry(family).or_default()
+    }
+}
+
+/// Whether SFML can load `data` as a font
+fn is_loadable(data: &[u8]) -> bool {
+    // SAFETY: The font is dropped right away, while `data` is still alive
+    unsafe { Font::from_memory(data) }.is_ok()
+}
+
+/// Look for an installed font file in the directories fontconfig searches by default.
+///
+/// `query` is matched against file names without the extension, ignoring case, spaces,
+/// dashes and underscores, so `"Noto Sans CJK Regular"` finds `NotoSansCJK-Regular.ttc`.
+#[cfg(target_os = "linux")]
+pub fn find_system_font(query: &str) -> Option<PathBuf> {
+    let query = normalize_font_name(query);
+    font_dirs()
+        .iter()
+        .find_map(|dir| find_in_dir(dir, &query, 0))
+}
+
+#[cfg(target_os = "linux")]
+fn font_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+    ];
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match std::env::var_os("XDG_DATA_HOME") {
+        Some(data_home) => dirs.push(PathBuf::from(data_home).join("fonts")),
+        None => dirs.extend(home.as_ref().map(|h| h.join(".local/share/fonts"))),
+    }
+    dirs.extend(home.map(|h| h.join(".fonts")));
+    dirs
+}
+
+#[cfg(target_os = "linux")]
+fn find_in_dir(dir: &Path, query: &str, depth: u32) -> Option<PathBuf> {
+    // Font directories are shallow, this only guards against symlink loops
+    const MAX_DEPTH: u32 = 8;
+    let mut subdirs = Vec::new();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            subdirs.push(path);
+            continue;
+        }
+        let is_font = path.extension().is_some_and(|ext| {
+            ["ttf", "otf", "ttc"]
+                .iter()
+                .any(|font_ext| ext.eq_ignore_ascii_case(font_ext))
+        });
+        let matches = path
+            .file_stem()
+            .is_some_and(|stem| normalize_font_name(&stem.to_string_lossy()) == query);
+        if is_font && matches {
+            return Some(path);
+        }
+    }
+    if depth >= MAX_DEPTH {
+        return None;
+    }
+    subdirs
+        .iter()
+        .find_map(|subdir| find_in_dir(subdir, query, depth + 1))
+}
+
+#[cfg(target_os = "linux")]
+fn normalize_font_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, ' ' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Error when loading a font for a [`FontSet`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum FontError {
+    /// Failed to read the font file
+    Io(PathBuf, std::io::Error),
+    /// The file is not a font SFML can load
+    Invalid(PathBuf),
+    /// No system font matched the query
+    NotFound(String),
+}
+
+impl std::fmt::Display for FontError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FontError::Io(path, e) => {
+                f.write_fmt(format_args!("Failed to read {}: {e}", path.display()))
+            }
+            FontError::Invalid(path) => {
+                f.write_fmt(format_args!("{} is not a valid font", path.display()))
+            }
+            FontError::NotFound(query) => {
+                f.write_fmt(format_args!("No system font matches {query:?}"))
+            }
+        }
+    }
+}
+
+impl std::error::Error for FontError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FontError::Io(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl SfEgui {
+    /// Make egui use the fonts of `fonts`
+    pub fn install_fonts(&self, fonts: FontSet) {
+        self.ctx.set_fonts(fonts.build());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn font_set(names: &[&str]) -> FontSet {
+        names.iter().fold(FontSet::empty(), |fonts, &name| {
+            fonts.with_data(name, FontData::from_static(&[]))
+        })
+    }
+
+    fn chain(definitions: &FontDefinitions, family: FontFamily) -> Vec<&str> {
+        definitions.families[&family]
+            .iter()
+            .map(String::as_str)
+            .collect()
+    }
+
+    #[test]
+    fn chain_order() {
+        let definitions = font_set(&["a", "b", "c", "d"])
+            .fallback(FontFamily::Proportional, "a")
+            .fallback(FontFamily::Proportional, "b")
+            .primary(FontFamily::Proportional, "c")
+            // Moves "a" to the end instead of adding it twice
+            .fallback(FontFamily::Proportional, "a")
+            .chain(FontFamily::Monospace, &["d", "b"])
+            .build();
+        assert_eq!(
+            chain(&definitions, FontFamily::Proportional),
+            ["c", "b", "a"]
+        );
+        assert_eq!(chain(&definitions, FontFamily::Monospace), ["d", "b"]);
+    }
+
+    #[test]
+    fn build_drops_fonts_that_were_never_loaded() {
+        let definitions = font_set(&["loaded"])
+            .chain(FontFamily::Proportional, &["missing", "loaded", "typo"])
+            .build();
+        assert_eq!(chain(&definitions, FontFamily::Proportional), ["loaded"]);
+    }
+
+    #[test]
+    fn default_fonts_stay_in_the_chain() {
+        let definitions = FontSet::new()
+            .with_data("extra", FontData::from_static(&[]))
+            .fallback(FontFamily::Proportional, "extra")
+            .build();
+        let defaults = FontDefinitions::default();
+        let mut expected = chain(&defaults, FontFamily::Proportional);
+        expected.push("extra");
+        assert_eq!(chain(&definitions, FontFamily::Proportional), expected);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn font_names_are_normalized() {
+        assert_eq!(
+            normalize_font_name("Noto Sans CJK Regular"),
+            normalize_font_name("NotoSansCJK-Regular")
+        );
+        assert_eq!(normalize_font_name("DejaVu_Sans-Bold"), "dejavusansbold");
+        assert_ne!(
+            normalize_font_name("Noto Sans"),
+            normalize_font_name("Noto Serif")
+        );
+    }
+}
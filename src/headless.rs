@@ -0,0 +1,340 @@
+//! Running egui-sfml uis without a display, for testing
+//!
+//! [`Harness`] drives an [`SfEgui`] with a [`MockWindow`] and a [`MemoryClipboard`],
+//! so ui logic, [`ViewportCommand`] handling and cursor changes can be tested on
+//! machines without a display.
+//!
+//! Textures are not uploaded, and [`SfEgui::draw`] can't be used, but [`Harness::snapshot`]
+//! renders the ui on the CPU.
+
+use {
+    crate::{
+        snapshot::{rasterize, SnapshotTextures},
+        DrawInput, EventResult, MemoryClipboard, PassError, SfEgui, Window,
+    },
+    egui::{Color32, ColorImage, Context, CursorIcon, IconData, TexturesDelta, ViewportCommand},
+    sfml::{
+        system::{Vector2i, Vector2u},
+        window::{Event, Style},
+    },
+};
+
+/// A [`Window`] that only keeps track of what was done to it
+#[derive(Debug, Clone)]
+pub struct MockWindow {
+    /// Inner size of the window
+    pub size: Vector2u,
+    /// Position of the window on the desktop
+    pub position: Vector2i,
+    /// Whether the window has input focus
+    pub focused: bool,
+    /// Title of the window
+    pub title: String,
+    /// Whether the window is visible
+    pub visible: bool,
+    /// Whether the window is open
+    pub open: bool,
+    /// The style the window was last recreated with, if any
+    pub style: Option<Style>,
+    /// Size of the last icon that was set, if any
+    pub icon_size: Option<(u32, u32)>,
+    /// The cursor shown on the window, `None` if it's hidden
+    pub cursor: Option<CursorIcon>,
+    /// Whether the mouse cursor is confined to the window
+    pub cursor_grabbed: bool,
+    /// The last position the mouse cursor was moved to, if any
+    pub mouse_position: Option<Vector2i>,
+    /// Size of the desktop
+    pub desktop_size: Vector2u,
+    /// Every [`ViewportCommand`] applied to the window, in order
+    pub commands: Vec<ViewportCommand>,
+    /// The egui textures, kept up to date with the output of every pass
+    pub textures: SnapshotTextures,
+}
+
+impl MockWindow {
+    /// Create an open, visible and focused window with the inner size `size`
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            size: size.into(),
+            position: Vector2i::default(),
+            focused: true,
+            title: String::new(),
+            visible: true,
+            open: true,
+            style: None,
+            icon_size: None,
+            cursor: Some(CursorIcon::Default),
+            cursor_grabbed: false,
+            mouse_position: None,
+            desktop_size: Vector2u::new(1920, 1080),
+            commands: Vec::new(),
+            textures: SnapshotTextures::default(),
+        }
+    }
+}
+
+impl Window for MockWindow {
+    fn size(&self) -> Vector2u {
+        self.size
+    }
+    fn set_size(&mut self, size: Vector2u) {
+        self.size = size;
+    }
+    fn position(&self) -> Vector2i {
+        self.position
+    }
+    fn set_position(&mut self, position: Vector2i) {
+        self.position = position;
+    }
+    fn has_focus(&self) -> bool {
+        self.focused
+    }
+    fn set_title(&mut self, title: &str) {
+        title.clone_into(&mut self.title);
+    }
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+    fn close(&mut self) {
+        self.open = false;
+    }
+    fn recreate(&mut self, size: Vector2u, title: &str, style: Style) {
+        self.size = size;
+        self.set_title(title);
+        self.style = Some(style);
+    }
+    fn set_icon(&mut self, icon: &IconData) {
+        self.icon_size = Some((icon.width, icon.height));
+    }
+    fn set_mouse_cursor(&mut self, icon: Option<CursorIcon>) {
+        self.cursor = icon;
+    }
+    fn set_mouse_cursor_grabbed(&mut self, grabbed: bool) {
+        self.cursor_grabbed = grabbed;
+    }
+    fn set_mouse_position(&mut self, position: Vector2i) {
+        self.mouse_position = Some(position);
+    }
+    fn desktop_size(&self) -> Vector2u {
+        self.desktop_size
+    }
+    fn max_texture_side(&self) -> usize {
+        8192
+    }
+    fn supports_textures(&self) -> bool {
+        false
+    }
+    fn command_applied(&mut self, cmd: &ViewportCommand) {
+        self.commands.push(cmd.clone());
+    }
+    fn textures_updated(&mut self, delta: &TexturesDelta) {
+        self.textures.update(delta);
+    }
+}
+
+/// Runs an egui ui against a [`MockWindow`]
+pub struct Harness {
+    /// The integration under test
+    pub sf_egui: SfEgui,
+    /// The window the ui runs in
+    pub window: MockWindow,
+}
+
+impl Harness {
+    /// Create a harness with a window of the inner size `size`
+    pub fn new(size: (u32, u32)) -> Self {
+        let window = MockWindow::new(size);
+        let mut sf_egui = SfEgui::new(&window);
+        sf_egui.set_clipboard(MemoryClipboard::default());
+        Self { sf_egui, window }
+    }
+    /// Give `event` to egui, as if it happened on the window
+    pub fn add_event(&mut self, event: &Event) -> EventResult {
+        match *event {
+            Event::Resized { width, height } => self.window.size = Vector2u::new(width, height),
+            Event::GainedFocus => self.window.focused = true,
+            Event::LostFocus => self.window.focused = false,
+            _ => {}
+        }
+        self.sf_egui.add_event(event)
+    }
+    /// Run an egui pass with `ui`
+    pub fn run(&mut self, mut ui: impl FnMut(&Context)) -> Result<DrawInput, PassError> {
+        self.sf_egui.run(&mut self.window, |_window, ctx| ui(ctx))
+    }
+    /// Run an egui pass with `ui` and the input of the next frame of `player`,
+    /// see [`SfEgui::replay_pass`].
+    ///
+    /// Returns `Ok(None)` if all the frames were replayed.
+    #[cfg(feature = "recording")]
+    pub fn replay(
+        &mut self,
+        player: &mut crate::recording::Player,
+        mut ui: impl FnMut(&Context),
+    ) -> Result<Option<DrawInput>, PassError> {
+        self.sf_egui
+            .replay_pass(&mut self.window, player, |_window, ctx| ui(ctx))
+    }
+    /// Render the output of a pass on the CPU, on a background of `clear_color`
+    pub fn snapshot(&self, input: DrawInput, clear_color: Color32) -> ColorImage {
+        let [w, h] = [self.window.size.x as usize, self.window.size.y as usize];
+        let mut image = ColorImage::new([w, h], vec![clear_color; w * h]);
+        let primitives = self
+            .context()
+            .tessellate(input.shapes, input.pixels_per_point);
+        rasterize(
+            &mut image,
+            &primitives,
+            &self.window.textures,
+            input.pixels_per_point,
+        );
+        image
+    }
+    /// The egui context
+    pub fn context(&self) -> &Context {
+        self.sf_egui.context()
+    }
+    /// The current contents of the clipboard
+    pub fn clipboard(&self) -> String {
+        self.sf_egui.shared.clipboard.borrow_mut().get()
+    }
+    /// Replace the contents of the clipboard
+    pub fn set_clipboard(&mut self, text: &str) {
+        self.sf_egui.shared.clipboard.borrow_mut().set(text);
+    }
+    /// Take the [`ViewportCommand`]s applied to the window since the last call
+    pub fn take_commands(&mut self) -> Vec<ViewportCommand> {
+        std::mem::take(&mut self.window.commands)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, egui::vec2};
+
+    #[test]
+    fn viewport_commands_reach_the_window() {
+        let mut harness = Harness::new((800, 600));
+        harness
+            .run(|ctx| {
+                ctx.send_viewport_cmd(ViewportCommand::Title("Headless".into()));
+                ctx.send_viewport_cmd(ViewportCommand::InnerSize(vec2(640., 480.)));
+            })
+            .unwrap();
+        assert_eq!(
+            harness.take_commands(),
+            [
+                ViewportCommand::Title("Headless".into()),
+                ViewportCommand::InnerSize(vec2(640., 480.)),
+            ]
+        );
+        assert_eq!(harness.window.title, "Headless");
+        assert_eq!(harness.window.size, Vector2u::new(640, 480));
+        // Commands are only reported once
+        harness.run(|_ctx| {}).unwrap();
+        assert!(harness.take_commands().is_empty());
+    }
+
+    #[test]
+    fn maximizing_covers_the_desktop() {
+        let mut harness = Harness::new((800, 600));
+        harness.window.position = Vector2i::new(100, 50);
+        harness
+            .run(|ctx| ctx.send_viewport_cmd(ViewportCommand::Maximized(true)))
+            .unwrap();
+        assert_eq!(harness.window.size, harness.window.desktop_size);
+        assert_eq!(harness.window.position, Vector2i::new(0, 0));
+        harness
+            .run(|ctx| {
+                assert_eq!(ctx.input(|inp| inp.viewport().maximized), Some(true));
+                ctx.send_viewport_cmd(ViewportCommand::Maximized(false));
+            })
+            .unwrap();
+        assert_eq!(harness.window.size, Vector2u::new(800, 600));
+        assert_eq!(harness.window.position, Vector2i::new(100, 50));
+    }
+
+    #[test]
+    fn close_requests_can_be_cancelled() {
+        let mut harness = Harness::new((800, 600));
+        harness.add_event(&Event::Closed);
+        harness
+            .run(|ctx| {
+                assert!(ctx.input(|inp| inp.viewport().close_requested()));
+                ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+            })
+            .unwrap();
+        assert!(!harness.sf_egui.close_requested());
+
+        harness.add_event(&Event::Closed);
+        harness.run(|_ctx| {}).unwrap();
+        assert!(harness.sf_egui.close_requested());
+        // The request is only reported for one pass
+        harness.run(|_ctx| {}).unwrap();
+        assert!(!harness.sf_egui.close_requested());
+    }
+
+    #[test]
+    fn viewport_sizes_are_in_points() {
+        let mut harness = Harness::new((800, 600));
+        harness.context().set_zoom_factor(2.0);
+        harness
+            .run(|ctx| {
+                let rect = ctx.input(|inp| inp.viewport().inner_rect);
+                assert_eq!(rect.map(|r| r.size()), Some(vec2(400., 300.)));
+                ctx.send_viewport_cmd(ViewportCommand::InnerSize(vec2(320., 240.)));
+            })
+            .unwrap();
+        assert_eq!(harness.window.size, Vector2u::new(640, 480));
+    }
+
+    #[test]
+    fn resetting_the_zoom_keeps_the_configured_scale() {
+        let window = MockWindow::new((800, 600));
+        let sf_egui = SfEgui::builder()
+            .pixels_per_point(2.0)
+            .clipboard(MemoryClipboard::default())
+            .build(&window)
+            .unwrap();
+        let mut harness = Harness { sf_egui, window };
+        harness.context().set_zoom_factor(1.5);
+        let input = harness.run(|_ctx| {}).unwrap();
+        assert_eq!(input.pixels_per_point, 3.0);
+        // Like Ctrl+0
+        harness.context().set_zoom_factor(1.0);
+        let input = harness
+            .run(|ctx| {
+                let rect = ctx.input(|inp| inp.viewport().inner_rect);
+                assert_eq!(rect.map(|r| r.size()), Some(vec2(400., 300.)));
+            })
+            .unwrap();
+        assert_eq!(input.pixels_per_point, 2.0);
+    }
+
+    #[test]
+    fn hovering_a_text_edit_shows_the_text_cursor() {
+        let mut text = String::new();
+        let edit_rect = std::cell::Cell::new(egui::Rect::NOTHING);
+        let mut ui = |ctx: &Context| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                edit_rect.set(ui.text_edit_singleline(&mut text).rect);
+            });
+        };
+        let mut harness = Harness::new((400, 300));
+        harness.run(&mut ui).unwrap();
+        assert_eq!(harness.window.cursor, Some(CursorIcon::Default));
+
+        let pos = edit_rect.get().center();
+        harness.add_event(&Event::MouseMoved {
+            x: pos.x as i32,
+            y: pos.y as i32,
+        });
+        harness.run(&mut ui).unwrap();
+        assert_eq!(harness.window.cursor, Some(CursorIcon::Text));
+
+        harness.add_event(&Event::MouseMoved { x: 390, y: 290 });
+        harness.run(&mut ui).unwrap();
+        assert_eq!(harness.window.cursor, Some(CursorIcon::Default));
+    }
+}
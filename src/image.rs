@@ -0,0 +1,106 @@
+//! Conversions between SFML images and egui images
+//!
+//! SFML images hold unmultiplied RGBA pixels, while [`ColorImage`] stores them premultiplied
+//! by alpha. The `premultiplied` variants copy the pixel values as they are, for images whose
+//! pixels are already premultiplied, like the contents of a window egui was drawn to.
+
+use {
+    egui::{Color32, ColorImage, Context, TextureHandle, TextureOptions},
+    sfml::{
+        cpp::FBox,
+        graphics::{Image, Texture},
+        SfError, SfResult,
+    },
+};
+
+/// Convert an SFML image to an egui image
+pub fn to_color_image(image: &Image) -> ColorImage {
+    let size = image.size();
+    ColorImage::from_rgba_unmultiplied([size.x as usize, size.y as usize], image.pixel_data())
+}
+
+/// Convert an SFML image whose pixels are premultiplied by alpha to an egui image
+pub fn to_color_image_premultiplied(image: &Image) -> ColorImage {
+    let size = image.size();
+    ColorImage::from_rgba_premultiplied([size.x as usize, size.y as usize], image.pixel_data())
+}
+
+/// Download an SFML texture into an egui image
+pub fn texture_to_color_image(texture: &Texture) -> SfResult<ColorImage> {
+    Ok(to_color_image(&*texture.copy_to_image()?))
+}
+
+/// Convert an egui image to an SFML image
+pub fn from_color_image(image: &ColorImage) -> Result<FBox<Image>, ImageError> {
+    let data: Vec<u8> = image
+        .pixels
+        .iter()
+        .flat_map(|c| c.to_srgba_unmultiplied())
+        .collect();
+    image_from_rgba(image.size, &data)
+}
+
+/// Convert an egui image to an SFML image, keeping the pixels premultiplied by alpha
+pub fn from_color_image_premultiplied(image: &ColorImage) -> Result<FBox<Image>, ImageError> {
+    let data: Vec<u8> = image.pixels.iter().flat_map(Color32::to_array).collect();
+    image_from_rgba(image.size, &data)
+}
+
+fn image_from_rgba([w, h]: [usize; 2], data: &[u8]) -> Result<FBox<Image>, ImageError> {
+    let pixels = data.len() / 4;
+    if w.checked_mul(h) != Some(pixels) || u32::try_from(w.max(h)).is_err() {
+        return Err(ImageError::SizeMismatch {
+            size: [w, h],
+            pixels,
+        });
+    }
+    // SAFETY: We just checked that `data` holds exactly `w * h` RGBA pixels
+    Ok(unsafe { Image::from_pixels(w as u32, h as u32, data) }?)
+}
+
+/// Error when converting an egui image to an SFML image
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ImageError {
+    /// The number of pixels of the image doesn't match its size
+    SizeMismatch {
+        /// The size of the image
+        size: [usize; 2],
+        /// The number of pixels the image holds
+        pixels: usize,
+    },
+    /// SFML failed to create the image
+    Sfml(SfError),
+}
+
+impl From<SfError> for ImageError {
+    fn from(src: SfError) -> Self {
+        Self::Sfml(src)
+    }
+}
+
+impl std::fmt::Display for ImageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImageError::SizeMismatch { size, pixels } => f.write_fmt(format_args!(
+                "Image of size {}x{} holds {pixels} pixels",
+                size[0], size[1]
+            )),
+            ImageError::Sfml(e) => f.write_fmt(format_args!("SFML error: {e}")),
+        }
+    }
+}
+
+impl std::error::Error for ImageError {}
+
+/// Load `image` as an egui texture.
+///
+/// egui manages the texture: it's freed when the last clone of the returned handle is dropped.
+pub fn load_texture_from_sfml_image(
+    ctx: &Context,
+    name: impl Into<String>,
+    image: &Image,
+    options: TextureOptions,
+) -> TextureHandle {
+    ctx.load_texture(name, to_color_image(image), options)
+}
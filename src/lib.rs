@@ -4,223 +4,127 @@
 
 #![warn(missing_docs)]
 
+mod builder;
+mod clipboard;
+pub mod fonts;
+pub mod headless;
+pub mod image;
+pub mod loaders;
+#[cfg(feature = "persistence")]
+pub mod persistence;
+#[cfg(feature = "recording")]
+pub mod recording;
 mod rendering;
+mod runner;
+mod sfml_viewport;
+pub mod snapshot;
+pub mod translate;
+mod user_textures;
+mod viewport;
+mod window;
 
-pub use {egui, sfml};
+pub use {
+    builder::{BuildError, SfEguiBuilder},
+    clipboard::{Clipboard, MemoryClipboard, SfmlClipboard},
+    egui,
+    runner::{run_app, App, Frame, RunError, WindowOptions},
+    sfml,
+    sfml_viewport::SfmlViewport,
+    smallvec,
+    user_textures::{uv_sprite, IntoUserTexture, FIRST_REGISTERED_TEXTURE_ID, PREMULTIPLIED_ALPHA},
+    window::Window,
+};
 use {
     egui::{
-        Context, CursorIcon, Modifiers, PointerButton, Pos2, RawInput, TextureId, ViewportCommand,
+        Context, CursorIcon, Pos2, RawInput, TextureId, ViewportCommand, ViewportEvent, ViewportId,
+        ViewportIdMap, ViewportInfo, ViewportOutput,
     },
     sfml::{
         cpp::FBox,
-        graphics::{RenderTarget as _, RenderWindow, Texture},
-        system::{Clock, Vector2, Vector2i},
-        window::{clipboard, mouse, Cursor, CursorType, Event, Key},
+        graphics::{RenderTarget as _, RenderTexture, RenderWindow, Texture},
+        system::{Clock, Vector2},
+        window::{Event, Style},
+    },
+    std::{
+        cell::{Cell, OnceCell, RefCell},
+        collections::HashMap,
+        rc::Rc,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
     },
-    std::collections::HashMap,
+    translate::{translate_event, CommandKey, TranslationState},
 };
 
-fn button_conv(button: mouse::Button) -> Option<PointerButton> {
-    let but = match button {
-        mouse::Button::Left => PointerButton::Primary,
-        mouse::Button::Right => PointerButton::Secondary,
-        mouse::Button::Middle => PointerButton::Middle,
-        _ => return None,
-    };
-    Some(but)
+/// Converts an SFML event to an egui event and adds it to the `RawInput`.
+fn handle_event(
+    raw_input: &mut egui::RawInput,
+    state: &mut TranslationState,
+    event: &sfml::window::Event,
+    shared: &Shared,
+) {
+    state.command_key = shared.command_key.get();
+    state.update(event);
+    if matches!(event, Event::GainedFocus) && shared.resync_modifiers.get() {
+        state.resync_from_keyboard();
+    }
+    for ev in translate_event(event, state) {
+        let ev = match ev {
+            // The translation layer doesn't read the clipboard, so fill in the pasted text here
+            egui::Event::Paste(text) if text.is_empty() => {
+                egui::Event::Paste(shared.clipboard.borrow_mut().get())
+            }
+            ev => ev,
+        };
+        raw_input.events.push(ev);
+    }
+    raw_input.modifiers = state.modifiers;
+    if let Event::Resized { width, height } = *event {
+        raw_input.screen_rect = Some(raw_input_screen_rect(width, height));
+    }
 }
 
-fn key_conv(code: Key) -> Option<egui::Key> {
-    use egui::Key as EKey;
-    Some(match code {
-        Key::Down => EKey::ArrowDown,
-        Key::Left => EKey::ArrowLeft,
-        Key::Right => EKey::ArrowRight,
-        Key::Up => EKey::ArrowUp,
-        Key::Escape => EKey::Escape,
-        Key::Tab => EKey::Tab,
-        Key::Backspace => EKey::Backspace,
-        Key::Enter => EKey::Enter,
-        Key::Space => EKey::Space,
-        Key::Insert => EKey::Insert,
-        Key::Delete => EKey::Delete,
-        Key::Home => EKey::Home,
-        Key::End => EKey::End,
-        Key::PageUp => EKey::PageUp,
-        Key::PageDown => EKey::PageDown,
-        Key::LBracket => EKey::OpenBracket,
-        Key::RBracket => EKey::CloseBracket,
-        Key::Num0 => EKey::Num0,
-        Key::Num1 => EKey::Num1,
-        Key::Num2 => EKey::Num2,
-        Key::Num3 => EKey::Num3,
-        Key::Num4 => EKey::Num4,
-        Key::Num5 => EKey::Num5,
-        Key::Num6 => EKey::Num6,
-        Key::Num7 => EKey::Num7,
-        Key::Num8 => EKey::Num8,
-        Key::Num9 => EKey::Num9,
-        Key::A => EKey::A,
-        Key::B => EKey::B,
-        Key::C => EKey::C,
-        Key::D => EKey::D,
-        Key::E => EKey::E,
-        Key::F => EKey::F,
-        Key::G => EKey::G,
-        Key::H => EKey::H,
-        Key::I => EKey::I,
-        Key::J => EKey::J,
-        Key::K => EKey::K,
-        Key::L => EKey::L,
-        Key::M => EKey::M,
-        Key::N => EKey::N,
-        Key::O => EKey::O,
-        Key::P => EKey::P,
-        Key::Q => EKey::Q,
-        Key::R => EKey::R,
-        Key::S => EKey::S,
-        Key::T => EKey::T,
-        Key::U => EKey::U,
-        Key::V => EKey::V,
-        Key::W => EKey::W,
-        Key::X => EKey::X,
-        Key::Y => EKey::Y,
-        Key::Z => EKey::Z,
-        Key::F1 => EKey::F1,
-        Key::F2 => EKey::F2,
-        Key::F3 => EKey::F3,
-        Key::F4 => EKey::F4,
-        Key::F5 => EKey::F5,
-        Key::F6 => EKey::F6,
-        Key::F7 => EKey::F7,
-        Key::F8 => EKey::F8,
-        Key::F9 => EKey::F9,
-        Key::F10 => EKey::F10,
-        Key::F11 => EKey::F11,
-        Key::F12 => EKey::F12,
-        Key::Equal => EKey::Equals,
-        Key::Hyphen => EKey::Minus,
-        Key::Slash => EKey::Slash,
-        Key::Tilde => EKey::Backtick,
-        _ => return None,
-    })
+/// Whether `pos` is over an egui area, as of the last pass
+fn pointer_over_egui(ctx: &Context, pos: Pos2) -> bool {
+    match ctx.layer_id_at(pos) {
+        // Panels live on the background layer, which covers the whole screen.
+        // egui doesn't expose the area not covered by panels, so ask it about
+        // the last known pointer position instead.
+        Some(layer) if layer.order == egui::Order::Background => ctx.is_pointer_over_area(),
+        Some(_) => true,
+        None => false,
+    }
 }
 
-fn modifier(alt: bool, ctrl: bool, shift: bool) -> egui::Modifiers {
-    egui::Modifiers {
-        alt,
-        ctrl,
-        shift,
-        command: ctrl,
-        mac_cmd: false,
-    }
+/// Whether egui is interested in an event. Returned by [`SfEgui::add_event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventResult {
+    /// egui uses the event, so the rest of the application should probably ignore it
+    Consumed,
+    /// egui is not interested in the event
+    PassThrough,
 }
 
-/// Converts an SFML event to an egui event and adds it to the `RawInput`.
-fn handle_event(raw_input: &mut egui::RawInput, event: &sfml::window::Event) {
-    match *event {
-        Event::KeyPressed {
-            code,
-            alt,
-            ctrl,
-            shift,
-            system: _,
-            scan: _,
-        } => {
-            if ctrl {
-                match code {
-                    Key::V => raw_input
-                        .events
-                        .push(egui::Event::Text(clipboard::get_string())),
-                    Key::C => raw_input.events.push(egui::Event::Copy),
-                    Key::X => raw_input.events.push(egui::Event::Cut),
-                    _ => {}
-                }
-            }
-            if let Some(key) = key_conv(code) {
-                raw_input.events.push(egui::Event::Key {
-                    key,
-                    modifiers: modifier(alt, ctrl, shift),
-                    pressed: true,
-                    repeat: false,
-                    physical_key: None,
-                });
-            }
-        }
-        Event::KeyReleased {
-            code,
-            alt,
-            ctrl,
-            shift,
-            system: _,
-            scan: _,
-        } => {
-            if let Some(key) = key_conv(code) {
-                raw_input.events.push(egui::Event::Key {
-                    key,
-                    modifiers: modifier(alt, ctrl, shift),
-                    pressed: false,
-                    repeat: false,
-                    physical_key: None,
-                });
-            }
-        }
-        Event::MouseMoved { x, y } => {
-            raw_input
-                .events
-                .push(egui::Event::PointerMoved(Pos2::new(x as f32, y as f32)));
-        }
-        Event::MouseButtonPressed { x, y, button } => {
-            if let Some(button) = button_conv(button) {
-                raw_input.events.push(egui::Event::PointerButton {
-                    pos: Pos2::new(x as f32, y as f32),
-                    button,
-                    pressed: true,
-                    modifiers: Modifiers::default(),
-                });
-            }
-        }
-        Event::MouseButtonReleased { x, y, button } => {
-            if let Some(button) = button_conv(button) {
-                raw_input.events.push(egui::Event::PointerButton {
-                    pos: Pos2::new(x as f32, y as f32),
-                    button,
-                    pressed: false,
-                    modifiers: Modifiers::default(),
-                });
-            }
-        }
-        Event::TextEntered { unicode } => {
-            if !unicode.is_control() {
-                raw_input
-                    .events
-                    .push(egui::Event::Text(unicode.to_string()));
-            }
-        }
-        Event::MouseWheelScrolled { delta, .. } => {
-            if sfml::window::Key::LControl.is_pressed() {
-                raw_input
-                    .events
-                    .push(egui::Event::Zoom(if delta > 0.0 { 1.1 } else { 0.9 }));
-            }
-        }
-        Event::Resized { width, height } => {
-            raw_input.screen_rect = Some(raw_input_screen_rect(width, height));
-        }
-        _ => {}
+impl EventResult {
+    /// Whether egui uses the event
+    pub fn is_consumed(self) -> bool {
+        self == Self::Consumed
     }
 }
 
 /// Creates a `RawInput` that fits the window.
-fn make_raw_input(window: &RenderWindow) -> RawInput {
+fn make_raw_input(window: &dyn Window) -> RawInput {
     let Vector2 { x: w, y: h } = window.size();
     RawInput {
         screen_rect: Some(raw_input_screen_rect(w, h)),
-        max_texture_side: Some(Texture::maximum_size() as usize),
+        max_texture_side: Some(window.max_texture_side()),
         ..Default::default()
     }
 }
 
+/// The screen rect of a window of `w`x`h` pixels.
+///
+/// The screen rects in the raw input are kept in pixels until [`pixels_to_points`]
+/// converts them right before the pass.
 fn raw_input_screen_rect(w: u32, h: u32) -> egui::Rect {
     egui::Rect {
         min: Pos2::new(0., 0.),
@@ -228,95 +132,456 @@ fn raw_input_screen_rect(w: u32, h: u32) -> egui::Rect {
     }
 }
 
-/// A source for egui user textures.
+/// The pixels per point egui will use in the next pass of the viewport described by `info`.
 ///
-/// You can create a struct that contains all the necessary information to get a user texture from
-/// an id, and implement this trait for it.
-pub trait UserTexSource {
-    /// Get the texture that corresponds to `id`.
-    ///
-    /// Returns (width, height, texture).
-    fn get_texture(&mut self, id: u64) -> (f32, f32, &Texture);
+/// SFML doesn't report the scale factor of the monitor, so the native pixels per point are
+/// 1 unless set with [`SfEguiBuilder::pixels_per_point`].
+fn next_pixels_per_point(ctx: &Context, info: &ViewportInfo) -> f32 {
+    info.native_pixels_per_point.unwrap_or(1.0) * ctx.zoom_factor()
 }
 
-/// A dummy texture source in case you don't care about providing user textures
-struct DummyTexSource {
-    tex: FBox<Texture>,
+/// Converts the positions in `raw_input` from window pixels to egui points.
+///
+/// `screen_rect` is the last known screen rect in pixels. It's sent with every pass,
+/// because its size in points changes with the scale.
+fn pixels_to_points(raw_input: &mut RawInput, screen_rect: &mut egui::Rect, pixels_per_point: f32) {
+    if let Some(rect) = raw_input.screen_rect {
+        *screen_rect = rect;
+    }
+    raw_input.screen_rect = Some(*screen_rect / pixels_per_point);
+    for event in &mut raw_input.events {
+        match event {
+            egui::Event::PointerMoved(pos) | egui::Event::PointerButton { pos, .. } => {
+                *pos = *pos / pixels_per_point;
+            }
+            _ => {}
+        }
+    }
 }
 
-impl Default for DummyTexSource {
-    fn default() -> Self {
-        Self {
-            tex: Texture::new().unwrap(),
-        }
+/// Rescales the viewport info of the current pass, if egui applied a zoom factor that was
+/// still pending when the input was converted with `input_pixels_per_point`.
+///
+/// egui does the same for the screen rect, so this keeps the two in agreement.
+fn apply_pending_zoom(ctx: &Context, input_pixels_per_point: f32) {
+    let ppp = ctx.pixels_per_point();
+    if ppp != input_pixels_per_point {
+        ctx.input_mut(|inp| {
+            for info in inp.raw.viewports.values_mut() {
+                viewport::rescale_info(info, input_pixels_per_point / ppp);
+            }
+        });
     }
 }
 
-impl UserTexSource for DummyTexSource {
-    fn get_texture(&mut self, _id: u64) -> (f32, f32, &Texture) {
-        (0., 0., &self.tex)
+/// Applies a texture delta from egui to the managed textures
+fn update_textures(
+    textures: &mut TextureMap,
+    textures_delta: &egui::TexturesDelta,
+) -> Result<(), TextureCreateError> {
+    for (id, delta) in &textures_delta.set {
+        let tex = textures
+            .entry(*id)
+            .or_insert_with(|| Texture::new().unwrap());
+        rendering::update_tex_from_delta(tex, delta)?;
+    }
+    for id in &textures_delta.free {
+        textures.remove(id);
     }
+    Ok(())
+}
+
+/// Carries out the platform commands egui outputs at the end of a pass
+fn run_output_commands(clipboard: &mut dyn Clipboard, commands: Vec<egui::OutputCommand>) {
+    for cmd in commands {
+        match cmd {
+            egui::OutputCommand::CopyText(txt) => {
+                clipboard.set(&txt);
+            }
+            egui::OutputCommand::CopyImage(_img) => {
+                eprintln!("egui-sfml: Unimplemented image copy");
+            }
+            egui::OutputCommand::OpenUrl(_url) => {
+                eprintln!("egui-sfml: Unimplemented url open");
+            }
+        }
+    }
+}
+
+/// How often [`SfEgui::wait_events`] checks for new events
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A source for egui user textures.
+///
+/// Usually, registering textures with [`SfEgui::register_texture`] is simpler.
+///
+/// You can create a struct that contains all the necessary information to get a user texture from
+/// an id, and implement this trait for it.
+pub trait UserTexSource {
+    /// Get the texture that corresponds to `id`.
+    ///
+    /// Returns (width, height, texture).
+    fn get_texture(&mut self, id: u64) -> (f32, f32, &Texture);
 }
 
 type TextureMap = HashMap<TextureId, FBox<Texture>>;
+type RenderTextureMap = HashMap<TextureId, FBox<RenderTexture>>;
 
 /// `Egui` integration for SFML.
 pub struct SfEgui {
     clock: FBox<Clock>,
     ctx: Context,
     raw_input: RawInput,
-    textures: TextureMap,
-    last_window_pos: Vector2i,
-    cursors: Cursors,
+    window_state: viewport::WindowState,
+    translation: TranslationState,
+    shared: Rc<Shared>,
+    multi_viewport: bool,
+    ime: Option<egui::output::IMEOutput>,
+    pending_screenshots: Vec<egui::UserData>,
+    viewport_commands: Vec<(ViewportId, ViewportCommand)>,
+    repaint_delay: Duration,
+    /// The earliest repaint requested since the last pass, see [`Self::wait_events`]
+    repaint_requested: Arc<Mutex<Option<Instant>>>,
+    next_user_texture: u64,
+    /// The last known screen rect, in pixels
+    screen_rect: egui::Rect,
+    manage_cursor: bool,
+    continuous_repaint: bool,
+    preserve_gl_states: bool,
+    #[cfg(feature = "recording")]
+    recording: Option<recording::Recording>,
 }
 
-struct Cursors {
-    arrow: FBox<Cursor>,
-    horizontal: FBox<Cursor>,
-    vertical: FBox<Cursor>,
-    hand: FBox<Cursor>,
-    cross: FBox<Cursor>,
-    text: FBox<Cursor>,
+/// State that is shared between the root window and the windows of child viewports
+pub(crate) struct Shared {
+    textures: RefCell<TextureMap>,
+    clipboard: RefCell<Box<dyn Clipboard>>,
+    children: RefCell<viewport::Children>,
+    resync_modifiers: Cell<bool>,
+    command_key: Cell<CommandKey>,
+    /// Drawn in place of missing textures, created when first needed
+    placeholder: OnceCell<Option<FBox<Texture>>>,
+    /// The render textures of [`SfmlViewport`]s
+    render_textures: RefCell<RenderTextureMap>,
 }
 
-impl Default for Cursors {
-    fn default() -> Self {
-        Self {
-            arrow: Cursor::from_system(CursorType::Arrow).unwrap(),
-            horizontal: Cursor::from_system(CursorType::SizeHorizontal).unwrap(),
-            vertical: Cursor::from_system(CursorType::SizeVertical).unwrap(),
-            hand: Cursor::from_system(CursorType::Hand).unwrap(),
-            cross: Cursor::from_system(CursorType::Cross).unwrap(),
-            text: Cursor::from_system(CursorType::Text).unwrap(),
-        }
+impl Shared {
+    fn placeholder(&self) -> Option<&Texture> {
+        self.placeholder
+            .get_or_init(rendering::placeholder_texture)
+            .as_deref()
     }
 }
 
+/// The cursor to show for `icon`, `None` if the cursor should be hidden
+fn shown_cursor(icon: CursorIcon, visible: bool) -> Option<CursorIcon> {
+    (visible && icon != CursorIcon::None).then_some(icon)
+}
+
+/// What happened while drawing the ui with [`SfEgui::draw`]
+#[derive(Debug, Clone, Default)]
+pub struct DrawReport {
+    /// The textures the ui uses that couldn't be found.
+    ///
+    /// They were drawn as a checkerboard instead.
+    pub missing_textures: Vec<TextureId>,
+}
+
 /// Data required to draw the egui ui
 pub struct DrawInput {
     shapes: Vec<egui::epaint::ClippedShape>,
     pixels_per_point: f32,
+    repaint_delay: Duration,
+}
+
+impl DrawInput {
+    /// How long egui can wait before it needs another pass.
+    ///
+    /// This is [`Duration::ZERO`] if egui wants to repaint right away (for example during
+    /// animations), and [`Duration::MAX`] if it only needs to repaint on new input.
+    ///
+    /// See also [`SfEgui::wait_events`].
+    pub fn repaint_delay(&self) -> Duration {
+        self.repaint_delay
+    }
 }
 
 impl SfEgui {
     /// Create a new `SfEgui`.
     ///
     /// The size of the egui ui will be the same as `window`'s size.
-    pub fn new(window: &RenderWindow) -> Self {
+    ///
+    /// Use [`Self::builder`] to configure it.
+    pub fn new(window: &dyn Window) -> Self {
+        let sf_egui = Self::with_context(Context::default(), window);
+        sf_egui.install_repaint_callback();
+        sf_egui
+    }
+    /// Configure a new `SfEgui`, see [`SfEguiBuilder`]
+    pub fn builder() -> SfEguiBuilder {
+        SfEguiBuilder::new()
+    }
+    fn with_context(ctx: Context, window: &dyn Window) -> Self {
+        let mut window_state = viewport::WindowState::default();
+        let ppp = next_pixels_per_point(&ctx, &window_state.info);
+        window_state.update_info(window, ppp);
         Self {
             clock: sfml::system::Clock::start().unwrap(),
             raw_input: make_raw_input(window),
-            ctx: Context::default(),
-            textures: TextureMap::default(),
-            last_window_pos: Vector2i::default(),
-            cursors: Cursors::default(),
+            ctx,
+            window_state,
+            translation: TranslationState::default(),
+            shared: Rc::new(Shared {
+                textures: RefCell::default(),
+                clipboard: RefCell::new(Box::new(SfmlClipboard)),
+                children: RefCell::default(),
+                resync_modifiers: Cell::new(false),
+                command_key: Cell::new(CommandKey::native()),
+                placeholder: OnceCell::new(),
+                render_textures: RefCell::default(),
+            }),
+            multi_viewport: false,
+            ime: None,
+            pending_screenshots: Vec::new(),
+            viewport_commands: Vec::new(),
+            repaint_delay: Duration::ZERO,
+            repaint_requested: Arc::default(),
+            next_user_texture: FIRST_REGISTERED_TEXTURE_ID,
+            screen_rect: egui::Rect::ZERO,
+            manage_cursor: true,
+            continuous_repaint: false,
+            preserve_gl_states: false,
+            #[cfg(feature = "recording")]
+            recording: None,
+        }
+    }
+    /// Makes [`Context::request_repaint`] calls end [`Self::wait_events`].
+    ///
+    /// This replaces the request repaint callback of the context.
+    fn install_repaint_callback(&self) {
+        let repaint_requested = Arc::clone(&self.repaint_requested);
+        self.ctx.set_request_repaint_callback(move |info| {
+            let Some(at) = Instant::now().checked_add(info.delay) else {
+                return;
+            };
+            let mut requested = repaint_requested.lock().unwrap();
+            *requested = Some(requested.map_or(at, |earlier: Instant| earlier.min(at)));
+        });
+    }
+    /// Use `clipboard` as the clipboard backend for copy and paste.
+    ///
+    /// By default, the system clipboard is used through [`SfmlClipboard`].
+    pub fn set_clipboard(&mut self, clipboard: impl Clipboard + 'static) {
+        *self.shared.clipboard.borrow_mut() = Box::new(clipboard);
+    }
+    /// Read the modifier keys from the keyboard when a window regains focus.
+    ///
+    /// The modifiers are tracked from the key events given to [`Self::add_event`],
+    /// so a modifier that was pressed while the window was unfocused is missed until
+    /// the next key event. Enabling this resynchronizes them, at the cost of reading
+    /// global keyboard state.
+    ///
+    /// Disabled by default.
+    pub fn set_resync_modifiers_on_focus(&mut self, enabled: bool) {
+        self.shared.resync_modifiers.set(enabled);
+    }
+    /// Use `key` for shortcuts like copy and paste.
+    ///
+    /// Defaults to the convention of the platform: the system key (Cmd) on macOS,
+    /// and Ctrl everywhere else. Overriding it is mostly useful for testing.
+    pub fn set_command_key(&mut self, key: CommandKey) {
+        self.shared.command_key.set(key);
+        self.translation.command_key = key;
+    }
+    /// Show egui viewports in their own windows.
+    ///
+    /// When enabled, [`Context::show_viewport_deferred`] and [`Context::show_viewport_immediate`]
+    /// create real SFML windows, which `SfEgui` manages entirely: it creates and destroys them,
+    /// polls their events, and draws them. Otherwise, viewports are embedded in the root window.
+    ///
+    /// Deferred viewports are run and drawn at the end of [`Self::run`] and [`Self::end_pass`].
+    /// Only the user textures registered with [`Self::register_texture`] are available
+    /// in child viewports.
+    ///
+    /// Several `SfEgui`s on the same thread can enable this, as long as they don't share
+    /// a context.
+    ///
+    /// Disabled by default.
+    pub fn set_multi_viewport(&mut self, enabled: bool) {
+        // Leaves the context alone unless this changes something, it might be shared
+        if self.multi_viewport == enabled {
+            return;
+        }
+        self.multi_viewport = enabled;
+        self.ctx.set_embed_viewports(!enabled);
+        if enabled {
+            viewport::install_immediate_renderer(&self.ctx, &self.shared);
+        } else {
+            viewport::uninstall_immediate_renderer(&self.ctx);
+            self.shared.children.borrow_mut().clear();
         }
     }
+    /// Show the cursor icons egui asks for, like a text cursor over text edits.
+    ///
+    /// Disable this to manage the mouse cursor of the root window yourself. This includes
+    /// hiding it, so [`ViewportCommand::CursorVisible`] has no effect either.
+    ///
+    /// Enabled by default.
+    pub fn set_manage_cursor(&mut self, enabled: bool) {
+        self.manage_cursor = enabled;
+    }
+    /// Repaint all the time, instead of only when egui needs to.
+    ///
+    /// This makes [`DrawInput::repaint_delay`] zero, so [`Self::wait_events`] never blocks.
+    /// Useful when the rest of the application redraws every frame anyway.
+    ///
+    /// Disabled by default.
+    pub fn set_continuous_repaint(&mut self, enabled: bool) {
+        self.continuous_repaint = enabled;
+    }
+    /// Save the OpenGL states before [`Self::draw`], and restore them after.
+    ///
+    /// Only needed when mixing egui with raw OpenGL calls, SFML itself doesn't
+    /// depend on the states egui changes.
+    ///
+    /// Disabled by default.
+    pub fn set_preserve_gl_states(&mut self, enabled: bool) {
+        self.preserve_gl_states = enabled;
+    }
     /// Convert an SFML event into an egui event and add it for later use by egui.
     ///
     /// Call this in an event polling loop for each event.
-    pub fn add_event(&mut self, event: &Event) {
-        handle_event(&mut self.raw_input, event);
+    ///
+    /// Returns whether egui is interested in the event, see [`Self::event_result`].
+    pub fn add_event(&mut self, event: &Event) -> EventResult {
+        handle_event(
+            &mut self.raw_input,
+            &mut self.translation,
+            event,
+            &self.shared,
+        );
+        self.window_state.update_info_from_event(event);
+        self.event_result(event)
+    }
+    /// Tells whether egui is interested in `event`, so the rest of the application can ignore it.
+    ///
+    /// This is decided based on the state of the last pass:
+    /// - Pointer events are consumed when egui is using the pointer (for example dragging
+    ///   a slider), or when they happen over an egui area.
+    /// - Keyboard and text events are consumed when egui wants keyboard input
+    ///   (for example a text edit has focus).
+    /// - Everything else passes through.
+    pub fn event_result(&self, event: &Event) -> EventResult {
+        let ctx = &self.ctx;
+        let ppp = ctx.pixels_per_point();
+        let consumed = match *event {
+            Event::MouseButtonPressed { x, y, .. } | Event::MouseWheelScrolled { x, y, .. } => {
+                ctx.is_using_pointer()
+                    || pointer_over_egui(ctx, Pos2::new(x as f32, y as f32) / ppp)
+            }
+            Event::MouseMoved { x, y } | Event::MouseButtonReleased { x, y, .. } => {
+                // Like `Context::wants_pointer_input`, but for the position of the event.
+                // A drag that started outside of egui doesn't get consumed.
+                ctx.is_using_pointer()
+                    || (pointer_over_egui(ctx, Pos2::new(x as f32, y as f32) / ppp)
+                        && !ctx.input(|inp| inp.pointer.any_down()))
+            }
+            Event::KeyPressed { .. } | Event::KeyReleased { .. } | Event::TextEntered { .. } => {
+                ctx.wants_keyboard_input()
+            }
+            _ => false,
+        };
+        if consumed {
+            EventResult::Consumed
+        } else {
+            EventResult::PassThrough
+        }
+    }
+    /// Waits until there are events on `rw`, or egui needs to repaint, then returns the events.
+    ///
+    /// The events are also added to egui, so there's no need to call [`Self::add_event`]
+    /// for them. How long to wait is decided by the repaint delay of the last pass
+    /// (see [`DrawInput::repaint_delay`]), and by [`Context::request_repaint`] calls from
+    /// other threads, unless that was disabled with [`SfEguiBuilder::repaint_callback`].
+    /// Events of child viewport windows also end the wait.
+    ///
+    /// SFML can't wait for an event with a timeout, so this polls in small steps,
+    /// which keeps the CPU usage of an idle ui negligible.
+    ///
+    /// Call this instead of polling the events of `rw` in your event loop.
+    pub fn wait_events(&mut self, rw: &mut RenderWindow) -> Vec<Event> {
+        let mut events = Vec::new();
+        let delay_deadline = Instant::now().checked_add(self.repaint_delay);
+        loop {
+            events.extend(std::iter::from_fn(|| rw.poll_event()));
+            let child_events = viewport::poll_children(&self.shared);
+            let deadline = match (delay_deadline, *self.repaint_requested.lock().unwrap()) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            let now = Instant::now();
+            if !events.is_empty() || child_events || deadline.is_some_and(|d| now >= d) {
+                break;
+            }
+            let step = deadline.map_or(WAIT_POLL_INTERVAL, |deadline| {
+                (deadline - now).min(WAIT_POLL_INTERVAL)
+            });
+            std::thread::sleep(step);
+        }
+        for event in &events {
+            self.add_event(event);
+        }
+        events
+    }
+    /// Whether the window was asked to close (an [`Event::Closed`] was given to
+    /// [`Self::add_event`] before the last pass), and the ui didn't cancel it with
+    /// [`ViewportCommand::CancelClose`].
+    ///
+    /// `SfEgui` doesn't close the window itself. The ui can check for close requests with
+    /// [`egui::ViewportInfo::close_requested`].
+    pub fn close_requested(&self) -> bool {
+        self.window_state.close_requested
+    }
+    /// Tell `SfEgui` the title and style the window was created with.
+    ///
+    /// SFML can't query these, but they are needed to carry out [`egui::ViewportCommand`]s that
+    /// recreate the window, like `Fullscreen` or `Resizable`.
+    /// If not set, an empty title and [`Style::DEFAULT`] are assumed.
+    ///
+    /// Note that recreating the window resets settings like vertical sync,
+    /// which you will have to apply again.
+    pub fn set_window_attributes(&mut self, title: &str, style: Style) {
+        self.window_state.set_attributes(title, style);
+    }
+    /// Add an IME (input method editor) event for later use by egui.
+    ///
+    /// SFML doesn't report IME composition state, so hosts that get it from the platform
+    /// (for example through the native window handle) can feed it in here.
+    ///
+    /// Note that some platforms also deliver the committed text as [`Event::TextEntered`].
+    /// In that case, don't forward [`egui::ImeEvent::Commit`], or the text will be entered twice.
+    pub fn add_ime_event(&mut self, event: egui::ImeEvent) {
+        self.raw_input.events.push(egui::Event::Ime(event));
+    }
+    /// Returns where egui wants the IME candidate window, as of the last pass.
+    ///
+    /// This is `None` if no text edit has focus, so the host should disable IME.
+    /// The rects are in window coordinates.
+    ///
+    /// SFML can't position the candidate window itself, so hosts that support IME
+    /// should apply this to the platform window after each pass.
+    pub fn ime_output(&self) -> Option<egui::output::IMEOutput> {
+        self.ime
+    }
+    /// Returns the [`ViewportCommand`]s of the last pass that target viewports
+    /// without a window.
+    ///
+    /// Only commands for [`ViewportId::ROOT`] are applied to the window passed to
+    /// [`Self::run`] or [`Self::end_pass`]. Commands for other viewports end up here,
+    /// unless [multi-viewport support](Self::set_multi_viewport) is enabled and the viewport
+    /// has its own window. Hosts can implement them as they see fit.
+    pub fn viewport_commands(&self) -> &[(ViewportId, ViewportCommand)] {
+        &self.viewport_commands
     }
     /// Does a [`egui::Context::run`] to run your egui ui.
     ///
@@ -325,22 +590,37 @@ impl SfEgui {
     /// See [`egui::Context::request_discard`].
     ///
     /// The `f` parameter is a user supplied ui function that does the desired ui
-    pub fn run(
+    pub fn run<W: Window>(
         &mut self,
-        rw: &mut RenderWindow,
-        mut f: impl FnMut(&mut RenderWindow, &Context),
+        rw: &mut W,
+        mut f: impl FnMut(&mut W, &Context),
     ) -> Result<DrawInput, PassError> {
-        self.prepare_raw_input();
-        let out = self.ctx.run(self.raw_input.take(), |ctx| f(rw, ctx));
+        let ppp = self.prepare_raw_input();
+        let input = self.raw_input.take();
+        self.run_with_input(rw, input, |rw, ctx| {
+            apply_pending_zoom(ctx, ppp);
+            f(rw, ctx);
+        })
+    }
+    /// Runs a pass with `input`, and handles its output like [`Self::run`]
+    fn run_with_input<W: Window>(
+        &mut self,
+        rw: &mut W,
+        input: RawInput,
+        mut f: impl FnMut(&mut W, &Context),
+    ) -> Result<DrawInput, PassError> {
+        let out = self.ctx.run(input, |ctx| f(rw, ctx));
         self.handle_output(
             rw,
             out.platform_output,
             out.textures_delta,
             out.viewport_output,
         )?;
+        self.run_child_viewports(rw)?;
         Ok(DrawInput {
             shapes: out.shapes,
             pixels_per_point: out.pixels_per_point,
+            repaint_delay: self.repaint_delay,
         })
     }
 
@@ -351,12 +631,13 @@ impl SfEgui {
     ///
     /// If you call this, it should be paired with [`Self::end_pass`].
     pub fn begin_pass(&mut self) {
-        self.prepare_raw_input();
+        let ppp = self.prepare_raw_input();
         self.ctx.begin_pass(self.raw_input.take());
+        apply_pending_zoom(&self.ctx, ppp);
     }
 
     /// Ends an egui pass. Call [`Self::begin_pass`] first.
-    pub fn end_pass(&mut self, rw: &mut RenderWindow) -> Result<DrawInput, PassError> {
+    pub fn end_pass(&mut self, rw: &mut dyn Window) -> Result<DrawInput, PassError> {
         let out = self.ctx.end_pass();
         self.handle_output(
             rw,
@@ -364,120 +645,169 @@ impl SfEgui {
             out.textures_delta,
             out.viewport_output,
         )?;
+        self.run_child_viewports(rw)?;
         Ok(DrawInput {
             shapes: out.shapes,
             pixels_per_point: out.pixels_per_point,
+            repaint_delay: self.repaint_delay,
         })
     }
 
     fn handle_output(
         &mut self,
-        rw: &mut RenderWindow,
+        rw: &mut dyn Window,
         platform_output: egui::PlatformOutput,
         textures_delta: egui::TexturesDelta,
-        viewport_output: egui::ViewportIdMap<egui::ViewportOutput>,
+        viewport_output: ViewportIdMap<ViewportOutput>,
     ) -> Result<(), PassError> {
-        for (id, delta) in &textures_delta.set {
-            let tex = self
-                .textures
-                .entry(*id)
-                .or_insert_with(|| Texture::new().unwrap());
-            rendering::update_tex_from_delta(tex, delta)?;
+        rw.textures_updated(&textures_delta);
+        if rw.supports_textures() {
+            update_textures(&mut self.shared.textures.borrow_mut(), &textures_delta)?;
         }
-        for id in &textures_delta.free {
-            self.textures.remove(id);
+        self.viewport_commands.clear();
+        self.repaint_delay = Duration::MAX;
+        self.handle_viewport_output(rw, viewport_output);
+        let ppp = self.ctx.pixels_per_point();
+        self.window_state.enforce_size_limits(rw, ppp);
+        let next_ppp = next_pixels_per_point(&self.ctx, &self.window_state.info);
+        self.window_state.update_info(rw, next_ppp);
+        if self.manage_cursor {
+            rw.set_mouse_cursor(shown_cursor(
+                platform_output.cursor_icon,
+                self.window_state.cursor_visible,
+            ));
         }
-        let new_cursor = match platform_output.cursor_icon {
-            CursorIcon::Default => Some(&self.cursors.arrow),
-            CursorIcon::None => None,
-            CursorIcon::PointingHand | CursorIcon::Grab | CursorIcon::Grabbing => {
-                Some(&self.cursors.hand)
-            }
-            CursorIcon::Crosshair => Some(&self.cursors.cross),
-            CursorIcon::Text => Some(&self.cursors.text),
-            CursorIcon::ResizeHorizontal | CursorIcon::ResizeColumn => {
-                Some(&self.cursors.horizontal)
-            }
-            CursorIcon::ResizeVertical => Some(&self.cursors.vertical),
-            _ => Some(&self.cursors.arrow),
-        };
-        match new_cursor {
-            Some(cur) => {
-                rw.set_mouse_cursor_visible(true);
-                unsafe {
-                    rw.set_mouse_cursor(cur);
-                }
-            }
-            None => rw.set_mouse_cursor_visible(false),
+        if self.continuous_repaint {
+            self.repaint_delay = Duration::ZERO;
         }
-        for cmd in platform_output.commands {
-            match cmd {
-                egui::OutputCommand::CopyText(txt) => {
-                    clipboard::set_string(&txt);
-                }
-                egui::OutputCommand::CopyImage(_img) => {
-                    eprintln!("egui-sfml: Unimplemented image copy");
-                }
-                egui::OutputCommand::OpenUrl(_url) => {
-                    eprintln!("egui-sfml: Unimplemented url open");
-                }
-            }
+        self.ime = platform_output.ime.map(|ime| egui::output::IMEOutput {
+            rect: ime.rect * ppp,
+            cursor_rect: ime.cursor_rect * ppp,
+        });
+        run_output_commands(
+            &mut **self.shared.clipboard.borrow_mut(),
+            platform_output.commands,
+        );
+        Ok(())
+    }
+
+    fn handle_viewport_output(
+        &mut self,
+        rw: &mut dyn Window,
+        mut viewport_output: ViewportIdMap<ViewportOutput>,
+    ) {
+        // Sizes and positions in the commands are in the points of the last pass
+        let ppp = self.ctx.pixels_per_point();
+        if self.multi_viewport {
+            viewport::sync_children(
+                &mut self.shared.children.borrow_mut(),
+                &mut viewport_output,
+                &self.ctx,
+            );
         }
-        // TODO: Multi-viewport support
-        for (_, out) in viewport_output {
+        for (id, out) in viewport_output {
+            self.repaint_delay = self.repaint_delay.min(out.repaint_delay);
+            if id != ViewportId::ROOT {
+                // Nothing hosts this viewport, so leave it to the caller
+                self.viewport_commands
+                    .extend(out.commands.into_iter().map(|cmd| (id, cmd)));
+                continue;
+            }
             for cmd in out.commands {
                 match cmd {
-                    ViewportCommand::Close => rw.close(),
-                    ViewportCommand::Title(s) => rw.set_title(&s),
-                    ViewportCommand::Visible(visible) => {
-                        if !visible {
-                            self.last_window_pos = rw.position();
-                        }
-                        rw.set_visible(visible);
-                        if visible {
-                            rw.set_position(self.last_window_pos);
-                        }
-                    }
-                    ViewportCommand::Focus => {
-                        // This trick forces focus where `request_focus` would
-                        // only flash the tray icon.
-                        let rw_pos = rw.position();
-                        rw.set_visible(false);
-                        rw.set_visible(true);
-                        rw.set_position(rw_pos);
+                    // The frame is captured after it's drawn, see `Self::draw`
+                    ViewportCommand::Screenshot(user_data) => {
+                        self.pending_screenshots.push(user_data);
                     }
-                    _ => eprintln!("egui_sfml: Unhandled ViewportCommand: {cmd:?}"),
+                    cmd => self
+                        .window_state
+                        .apply_command(rw, &mut self.raw_input, cmd, ppp),
                 }
             }
         }
+    }
+
+    /// Runs and draws the deferred child viewports, if multi-viewport support is enabled
+    fn run_child_viewports(&mut self, rw: &mut dyn Window) -> Result<(), PassError> {
+        if !self.multi_viewport {
+            return Ok(());
+        }
+        let time = self.clock.elapsed_time().as_seconds() as f64;
+        for viewport_output in viewport::run_deferred(&self.ctx, &self.shared, time)? {
+            self.handle_viewport_output(rw, viewport_output);
+        }
         Ok(())
     }
 
-    fn prepare_raw_input(&mut self) {
+    /// Gets the raw input ready for the next pass, and returns the pixels per point
+    /// it was converted with
+    fn prepare_raw_input(&mut self) -> f32 {
+        // Requests made so far are part of the repaint delay this pass will report
+        *self.repaint_requested.lock().unwrap() = None;
         self.raw_input.time = Some(self.clock.elapsed_time().as_seconds() as f64);
-        // Update modifiers every frame, otherwise querying them (input.modifiers.*) doesn't seem
-        // up-to-date
-        self.raw_input.modifiers.alt = Key::LAlt.is_pressed() || Key::RAlt.is_pressed();
-        self.raw_input.modifiers.ctrl = Key::LControl.is_pressed() || Key::RControl.is_pressed();
-        self.raw_input.modifiers.shift = Key::LShift.is_pressed() || Key::RShift.is_pressed();
+        let ppp = next_pixels_per_point(&self.ctx, &self.window_state.info);
+        let info = self.window_state.info_for_pass(ppp);
+        // Viewport events are only reported for a single pass
+        self.window_state.info.events.clear();
+        self.window_state.close_requested = info.events.contains(&ViewportEvent::Close);
+        self.raw_input.viewports = std::iter::once((ViewportId::ROOT, info)).collect();
+        self.raw_input.modifiers = self.translation.modifiers;
+        pixels_to_points(&mut self.raw_input, &mut self.screen_rect, ppp);
+        #[cfg(feature = "recording")]
+        if let Some(recording) = &mut self.recording {
+            recording.frames.push(self.raw_input.clone());
+        }
+        ppp
     }
     /// Draw the ui to a `RenderWindow`.
     ///
-    /// Takes an optional [`UserTexSource`] to act as a user texture source.
+    /// Takes an optional [`UserTexSource`] to act as a user texture source,
+    /// for the user textures that weren't registered with [`Self::register_texture`].
+    ///
+    /// Textures that can't be found are drawn as a checkerboard, and reported in the result.
     pub fn draw(
         &mut self,
         input: DrawInput,
         window: &mut RenderWindow,
         user_tex_src: Option<&mut dyn UserTexSource>,
-    ) {
-        rendering::draw(
+    ) -> DrawReport {
+        if self.preserve_gl_states {
+            window.push_gl_states();
+        }
+        let missing_textures = rendering::draw(
             window,
             &self.ctx,
             input.shapes,
-            user_tex_src.unwrap_or(&mut DummyTexSource::default()),
-            &self.textures,
+            user_tex_src,
+            &self.shared,
             input.pixels_per_point,
-        )
+        );
+        if self.preserve_gl_states {
+            window.pop_gl_states();
+        }
+        if !self.pending_screenshots.is_empty() {
+            self.capture_screenshots(window);
+        }
+        DrawReport { missing_textures }
+    }
+    /// Delivers the screenshots requested with [`ViewportCommand::Screenshot`]
+    /// as input for the next pass.
+    fn capture_screenshots(&mut self, window: &RenderWindow) {
+        let image = match rendering::capture(window) {
+            Ok(image) => Arc::new(image),
+            Err(e) => {
+                eprintln!("egui_sfml: Failed to capture screenshot: {e}");
+                self.pending_screenshots.clear();
+                return;
+            }
+        };
+        for user_data in self.pending_screenshots.drain(..) {
+            self.raw_input.events.push(egui::Event::Screenshot {
+                viewport_id: ViewportId::ROOT,
+                user_data,
+                image: image.clone(),
+            });
+        }
     }
     /// Returns a handle to the egui context
     pub fn context(&self) -> &Context {
@@ -485,6 +815,16 @@ impl SfEgui {
     }
 }
 
+impl Drop for SfEgui {
+    fn drop(&mut self) {
+        // Closes the child windows, and stops the immediate viewport renderer from
+        // using them, even if the context lives on
+        if self.multi_viewport {
+            self.set_multi_viewport(false);
+        }
+    }
+}
+
 #[derive(Debug)]
 /// Error when failing to create a texture
 pub struct TextureCreateError {
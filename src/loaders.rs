@@ -0,0 +1,159 @@
+//! egui image loaders backed by SFML
+//!
+//! With these installed, `ui.image("file://path/to/image.png")` works without `egui_extras`.
+//! See [`SfEgui::install_image_loaders`].
+
+use {
+    crate::{image::to_color_image, SfEgui},
+    egui::{
+        load::{
+            generate_loader_id, Bytes, BytesLoadResult, BytesLoader, BytesPoll, ImageLoadResult,
+            ImageLoader, ImagePoll, LoadError, SizeHint,
+        },
+        ColorImage, Context,
+    },
+    sfml::graphics::Image,
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+    },
+};
+
+/// Loads the bytes of `file://` URIs from the file system
+#[derive(Default)]
+pub struct FileLoader {
+    cache: Mutex<HashMap<String, Result<Bytes, String>>>,
+}
+
+impl FileLoader {
+    /// Unique id of this loader
+    pub const ID: &'static str = generate_loader_id!(FileLoader);
+}
+
+impl BytesLoader for FileLoader {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    fn load(&self, _ctx: &Context, uri: &str) -> BytesLoadResult {
+        let Some(path) = uri.strip_prefix("file://") else {
+            return Err(LoadError::NotSupported);
+        };
+        let mut cache = self.cache.lock().unwrap();
+        let result = cache.entry(uri.to_owned()).or_insert_with(|| {
+            std::fs::read(path)
+                .map(|data| Bytes::Shared(data.into()))
+                .map_err(|e| format!("Failed to read {path}: {e}"))
+        });
+        match result {
+            Ok(bytes) => Ok(BytesPoll::Ready {
+                size: None,
+                bytes: bytes.clone(),
+                mime: None,
+            }),
+            Err(e) => Err(LoadError::Loading(e.clone())),
+        }
+    }
+    fn forget(&self, uri: &str) {
+        self.cache.lock().unwrap().remove(uri);
+    }
+    fn forget_all(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+    fn byte_size(&self) -> usize {
+        self.cache
+            .lock()
+            .unwrap()
+            .values()
+            .map(|result| match result {
+                Ok(bytes) => bytes.len(),
+                Err(e) => e.len(),
+            })
+            .sum()
+    }
+}
+
+/// Decodes images with SFML.
+///
+/// Supports the formats SFML supports: BMP, PNG, TGA, JPG, GIF, PSD, HDR and PIC.
+#[derive(Default)]
+pub struct SfmlImageLoader {
+    cache: Mutex<HashMap<String, Decoded>>,
+}
+
+impl SfmlImageLoader {
+    /// Unique id of this loader
+    pub const ID: &'static str = generate_loader_id!(SfmlImageLoader);
+}
+
+impl ImageLoader for SfmlImageLoader {
+    fn id(&self) -> &str {
+        Self::ID
+    }
+    fn load(&self, ctx: &Context, uri: &str, _size_hint: SizeHint) -> ImageLoadResult {
+        if let Some(result) = self.cache.lock().unwrap().get(uri) {
+            return poll_result(result);
+        }
+        match ctx.try_load_bytes(uri)? {
+            BytesPoll::Pending { size } => Ok(ImagePoll::Pending { size }),
+            BytesPoll::Ready { bytes, .. } => {
+                // Failures are cached too, so SFML doesn't try (and complain on stderr)
+                // to decode the same unsupported image every frame
+                let result = match Image::from_memory(&bytes) {
+                    Ok(sf_image) => Ok(Arc::new(to_color_image(&sf_image))),
+                    Err(_) => Err(uri.rsplit_once('.').map(|(_, ext)| ext.to_owned())),
+                };
+                let poll = poll_result(&result);
+                self.cache.lock().unwrap().insert(uri.to_owned(), result);
+                poll
+            }
+        }
+    }
+    fn forget(&self, uri: &str) {
+        self.cache.lock().unwrap().remove(uri);
+    }
+    fn forget_all(&self) {
+        self.cache.lock().unwrap().clear();
+    }
+    fn byte_size(&self) -> usize {
+        self.cache
+            .lock()
+            .unwrap()
+            .values()
+            .map(|result| match result {
+                Ok(image) => image.pixels.len() * 4,
+                Err(format) => format.as_ref().map_or(0, String::len),
+            })
+            .sum()
+    }
+}
+
+/// A decoded image, or the detected format of an image SFML can't decode
+type Decoded = Result<Arc<ColorImage>, Option<String>>;
+
+fn poll_result(result: &Decoded) -> ImageLoadResult {
+    match result {
+        Ok(image) => Ok(ImagePoll::Ready {
+            image: image.clone(),
+        }),
+        // Leave it to the other loaders
+        Err(format) => Err(LoadError::FormatNotSupported {
+            detected_format: format.clone(),
+        }),
+    }
+}
+
+impl SfEgui {
+    /// Install [`FileLoader`] and [`SfmlImageLoader`], so egui can show images from
+    /// `file://` URIs and `bytes://` URIs (see [`Context::include_bytes`]).
+    ///
+    /// Does nothing for loaders that are already installed.
+    pub fn install_image_loaders(&self) {
+        if !self.ctx.is_loader_installed(FileLoader::ID) {
+            self.ctx.add_bytes_loader(Arc::new(FileLoader::default()));
+        }
+        if !self.ctx.is_loader_installed(SfmlImageLoader::ID) {
+            self.ctx
+                .add_image_loader(Arc::new(SfmlImageLoader::default()));
+        }
+    }
+}
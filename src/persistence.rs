@@ -0,0 +1,162 @@
+//! Saving egui memory, window geometry and app state between runs
+//!
+//! Requires the `persistence` feature.
+
+use {
+    crate::SfEgui,
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    sfml::{
+        graphics::{RenderTarget as _, RenderWindow},
+        system::{Vector2i, Vector2u},
+    },
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Key under which the egui memory is stored
+const MEMORY_KEY: &str = "egui_memory_ron";
+/// Key under which the window geometry is stored
+const WINDOW_KEY: &str = "window";
+
+/// A key/value store for persisting state between runs
+pub trait Storage {
+    /// Get the value stored for `key`
+    fn get_string(&self, key: &str) -> Option<String>;
+    /// Store `value` for `key`
+    fn set_string(&mut self, key: &str, value: String);
+    /// Write the stored values to where they are persisted
+    fn flush(&mut self);
+}
+
+/// Get a value stored with [`set_value`]
+pub fn get_value<T: DeserializeOwned>(storage: &dyn Storage, key: &str) -> Option<T> {
+    let string = storage.get_string(key)?;
+    match ron::from_str(&string) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("egui_sfml: Failed to deserialize {key:?}: {e}");
+            None
+        }
+    }
+}
+
+/// Serialize `value` and store it for `key`
+pub fn set_value<T: Serialize>(storage: &mut dyn Storage, key: &str, value: &T) {
+    match ron::to_string(value) {
+        Ok(string) => storage.set_string(key, string),
+        Err(e) => eprintln!("egui_sfml: Failed to serialize {key:?}: {e}"),
+    }
+}
+
+/// A [`Storage`] backed by a RON file
+pub struct FileStorage {
+    path: PathBuf,
+    values: HashMap<String, String>,
+    dirty: bool,
+}
+
+impl FileStorage {
+    /// Load the storage from the file at `path`.
+    ///
+    /// If the file doesn't exist or can't be read, the storage starts out empty.
+    pub fn from_path(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let values = match std::fs::read_to_string(&path) {
+            Ok(string) => ron::from_str(&string).unwrap_or_else(|e| {
+                eprintln!("egui_sfml: Failed to parse {}: {e}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path,
+            values,
+            dirty: false,
+        }
+    }
+    /// The path of the file the storage is saved to
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Storage for FileStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.values.get(key).cloned()
+    }
+    fn set_string(&mut self, key: &str, value: String) {
+        if self.values.get(key) != Some(&value) {
+            self.values.insert(key.to_owned(), value);
+            self.dirty = true;
+        }
+    }
+    fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+        let string = match ron::ser::to_string_pretty(&self.values, Default::default()) {
+            Ok(string) => string,
+            Err(e) => {
+                eprintln!("egui_sfml: Failed to serialize storage: {e}");
+                return;
+            }
+        };
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        // Write to a temporary file first, so a crash doesn't leave a half-written file behind
+        let tmp_path = self.path.with_extension("ron.tmp");
+        let result =
+            std::fs::write(&tmp_path, string).and_then(|()| std::fs::rename(&tmp_path, &self.path));
+        match result {
+            Ok(()) => self.dirty = false,
+            Err(e) => eprintln!("egui_sfml: Failed to save {}: {e}", self.path.display()),
+        }
+    }
+}
+
+/// Size and position of a window
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// Position of the window
+    pub position: (i32, i32),
+    /// Inner size of the window
+    pub size: (u32, u32),
+}
+
+impl WindowGeometry {
+    /// Get the current geometry of `rw`
+    pub fn of(rw: &RenderWindow) -> Self {
+        let Vector2i { x, y } = rw.position();
+        let Vector2u { x: w, y: h } = rw.size();
+        Self {
+            position: (x, y),
+            size: (w, h),
+        }
+    }
+    /// Load the window geometry saved with [`WindowGeometry::save`]
+    pub fn load(storage: &dyn Storage) -> Option<Self> {
+        get_value(storage, WINDOW_KEY)
+    }
+    /// Save the window geometry to `storage`
+    pub fn save(&self, storage: &mut dyn Storage) {
+        set_value(storage, WINDOW_KEY, self);
+    }
+}
+
+impl SfEgui {
+    /// Load the egui memory (window positions, collapsed headers, scroll offsets, etc.)
+    /// saved with [`Self::save_memory`].
+    pub fn load_memory(&self, storage: &dyn Storage) {
+        if let Some(memory) = get_value(storage, MEMORY_KEY) {
+            self.context().memory_mut(|mem| *mem = memory);
+        }
+    }
+    /// Save the egui memory to `storage`.
+    pub fn save_memory(&self, storage: &mut dyn Storage) {
+        self.context()
+            .memory(|mem| set_value(storage, MEMORY_KEY, mem));
+    }
+}
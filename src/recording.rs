@@ -0,0 +1,241 @@
+//! Recording the input given to egui, and replaying it deterministically
+//!
+//! Requires the `recording` feature.
+
+use {
+    crate::{DrawInput, PassError, SfEgui, Window},
+    egui::{Context, FullOutput, RawInput},
+    serde::{Deserialize, Serialize},
+    std::path::Path,
+};
+
+/// The input of a sequence of egui passes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Recording {
+    /// The input of each pass, in order.
+    ///
+    /// [`RawInput::time`] holds the time of the pass, as given by the clock of [`SfEgui`].
+    pub frames: Vec<RawInput>,
+}
+
+impl Recording {
+    /// Load a recording saved with [`Recording::save`]
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, RecordingError> {
+        let string = std::fs::read_to_string(path)?;
+        Ok(ron::from_str(&string)?)
+    }
+    /// Save the recording to a RON file at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), RecordingError> {
+        let string = ron::ser::to_string_pretty(self, Default::default())?;
+        std::fs::write(path, string)?;
+        Ok(())
+    }
+}
+
+/// Error when loading or saving a [`Recording`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum RecordingError {
+    /// Failed to read or write the file
+    Io(std::io::Error),
+    /// Failed to serialize the recording
+    Serialize(ron::Error),
+    /// The file is not a valid recording
+    Deserialize(ron::error::SpannedError),
+}
+
+impl From<std::io::Error> for RecordingError {
+    fn from(src: std::io::Error) -> Self {
+        Self::Io(src)
+    }
+}
+
+impl From<ron::Error> for RecordingError {
+    fn from(src: ron::Error) -> Self {
+        Self::Serialize(src)
+    }
+}
+
+impl From<ron::error::SpannedError> for RecordingError {
+    fn from(src: ron::error::SpannedError) -> Self {
+        Self::Deserialize(src)
+    }
+}
+
+impl std::fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordingError::Io(e) => f.write_fmt(format_args!("I/O error: {e}")),
+            RecordingError::Serialize(e) => f.write_fmt(format_args!("Serialize error: {e}")),
+            RecordingError::Deserialize(e) => f.write_fmt(format_args!("Deserialize error: {e}")),
+        }
+    }
+}
+
+impl std::error::Error for RecordingError {}
+
+/// Replays a [`Recording`] pass by pass, without a window
+pub struct Player {
+    recording: Recording,
+    frame: usize,
+    fixed_step: Option<f64>,
+}
+
+impl Player {
+    /// Create a player that starts at the first frame of `recording`
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            recording,
+            frame: 0,
+            fixed_step: None,
+        }
+    }
+    /// Replace the recorded timestamps with a fake clock, that advances by `step` seconds
+    /// every frame, starting at zero.
+    ///
+    /// Useful when the ui depends on the time, and the replay should not depend on
+    /// how fast the recording was made.
+    pub fn with_fixed_step(mut self, step: f64) -> Self {
+        self.fixed_step = Some(step);
+        self
+    }
+    /// The index of the next frame to be replayed
+    pub fn frame(&self) -> usize {
+        self.frame
+    }
+    /// Whether all the frames were replayed
+    pub fn is_finished(&self) -> bool {
+        self.frame >= self.recording.frames.len()
+    }
+    /// Go back to the first frame
+    pub fn rewind(&mut self) {
+        self.frame = 0;
+    }
+    /// Get the input of the next frame, and advance to the frame after it.
+    ///
+    /// Returns `None` if all the frames were replayed.
+    pub fn next_input(&mut self) -> Option<RawInput> {
+        let mut input = self.recording.frames.get(self.frame)?.clone();
+        if let Some(step) = self.fixed_step {
+            input.time = Some(self.frame as f64 * step);
+        }
+        self.frame += 1;
+        Some(input)
+    }
+    /// Run an egui pass with the input of the next frame.
+    ///
+    /// This only runs the context, the output is left to the caller.
+    /// Use [`SfEgui::replay_pass`] to handle it like a live pass would.
+    ///
+    /// Returns `None` if all the frames were replayed.
+    pub fn step(&mut self, ctx: &Context, ui: impl FnMut(&Context)) -> Option<FullOutput> {
+        let input = self.next_input()?;
+        Some(ctx.run(input, ui))
+    }
+}
+
+impl SfEgui {
+    /// Start recording the input of every pass.
+    ///
+    /// Any recording in progress is discarded.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+    /// Stop recording, and return what was recorded.
+    ///
+    /// Returns `None` if no recording was in progress.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+    /// Like [`Self::run`], but with the input of the next frame of `player`
+    /// instead of the input gathered from events.
+    ///
+    /// The output is handled as usual: textures are updated, viewport commands are applied
+    /// to `window`, and the clipboard and cursor are set. `window` can be a
+    /// [`MockWindow`](crate::headless::MockWindow) to replay without a display.
+    ///
+    /// Returns `Ok(None)` if all the frames were replayed.
+    pub fn replay_pass<W: Window>(
+        &mut self,
+        window: &mut W,
+        player: &mut Player,
+        ui: impl FnMut(&mut W, &Context),
+    ) -> Result<Option<DrawInput>, PassError> {
+        let Some(input) = player.next_input() else {
+            return Ok(None);
+        };
+        self.run_with_input(window, input, ui).map(Some)
+    }
+}
+
+#[cfg(all(test, feature = "recording"))]
+mod tests {
+    use {
+        super::*,
+        crate::headless::Harness,
+        sfml::window::{mouse, Event},
+    };
+
+    /// A button that counts its clicks, and the times of the frames it was shown in
+    #[derive(Default)]
+    struct Counter {
+        clicks: u32,
+        times: Vec<f64>,
+        button_pos: egui::Pos2,
+    }
+
+    impl Counter {
+        fn ui(&mut self, ctx: &Context) {
+            // egui can run more than one pass per frame
+            let time = ctx.input(|inp| inp.time);
+            if self.times.last() != Some(&time) {
+                self.times.push(time);
+            }
+            egui::CentralPanel::default().show(ctx, |ui| {
+                let response = ui.button("Count");
+                if response.clicked() {
+                    self.clicks += 1;
+                }
+                self.button_pos = response.rect.center();
+            });
+        }
+    }
+
+    #[test]
+    fn replay_is_deterministic() {
+        let mut harness = Harness::new((400, 300));
+        let mut counter = Counter::default();
+        harness.sf_egui.start_recording();
+        harness.run(|ctx| counter.ui(ctx)).unwrap();
+        let (x, y) = (counter.button_pos.x as i32, counter.button_pos.y as i32);
+        let button = mouse::Button::Left;
+        harness.add_event(&Event::MouseMoved { x, y });
+        harness.run(|ctx| counter.ui(ctx)).unwrap();
+        harness.add_event(&Event::MouseButtonPressed { button, x, y });
+        harness.run(|ctx| counter.ui(ctx)).unwrap();
+        harness.add_event(&Event::MouseButtonReleased { button, x, y });
+        harness.run(|ctx| counter.ui(ctx)).unwrap();
+        let recording = harness.sf_egui.stop_recording().unwrap();
+        assert_eq!(counter.clicks, 1);
+
+        let path =
+            std::env::temp_dir().join(format!("egui_sfml_recording_{}.ron", std::process::id()));
+        recording.save(&path).unwrap();
+        let loaded = Recording::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.frames.len(), 4);
+
+        let mut replay_harness = Harness::new((400, 300));
+        let mut replayed = Counter::default();
+        let mut player = Player::new(loaded).with_fixed_step(0.25);
+        while replay_harness
+            .replay(&mut player, |ctx| replayed.ui(ctx))
+            .unwrap()
+            .is_some()
+        {}
+        assert!(player.is_finished());
+        assert_eq!(replayed.clicks, counter.clicks);
+        assert_eq!(replayed.button_pos, counter.button_pos);
+        assert_eq!(replayed.times, [0.0, 0.25, 0.5, 0.75]);
+    }
+}
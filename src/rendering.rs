@@ -1,9 +1,12 @@
 use {
-    crate::{TextureCreateError, TextureMap, UserTexSource},
-    egui::{epaint::Primitive, ImageData, TextureId},
-    sfml::graphics::{
-        blend_mode::Factor, BlendMode, Color, PrimitiveType, RenderStates, RenderTarget as _,
-        RenderWindow, Texture, Vertex,
+    crate::{Shared, TextureCreateError, UserTexSource},
+    egui::{epaint::Primitive, ColorImage, ImageData, TextureId},
+    sfml::{
+        cpp::FBox,
+        graphics::{
+            blend_mode::Factor, BlendMode, Color, PrimitiveType, RenderStates, RenderTarget as _,
+            RenderWindow, Texture, Vertex,
+        },
     },
 };
 
@@ -32,14 +35,40 @@ pub(super) fn update_tex_from_delta(
     Ok(())
 }
 
+/// A magenta and black checkerboard, drawn in place of missing textures
+pub(super) fn placeholder_texture() -> Option<FBox<Texture>> {
+    const SIZE: u32 = 8;
+    let pixels: Vec<u8> = (0..SIZE * SIZE)
+        .flat_map(|i| {
+            if (i % SIZE + i / SIZE).is_multiple_of(2) {
+                [255, 0, 255, 255]
+            } else {
+                [0, 0, 0, 255]
+            }
+        })
+        .collect();
+    let mut tex = Texture::new().ok()?;
+    tex.create(SIZE, SIZE).ok()?;
+    tex.update_from_pixels(&pixels, SIZE, SIZE, 0, 0);
+    Some(tex)
+}
+
+fn with_size(tex: &Texture) -> (f32, f32, &Texture) {
+    (tex.size().x as f32, tex.size().y as f32, tex)
+}
+
+/// Draws the ui, and returns the ids of the textures that couldn't be found
 pub(super) fn draw(
     window: &mut RenderWindow,
     egui_ctx: &egui::Context,
     shapes: Vec<egui::epaint::ClippedShape>,
-    user_tex_source: &mut dyn UserTexSource,
-    textures: &TextureMap,
+    mut user_tex_source: Option<&mut dyn UserTexSource>,
+    shared: &Shared,
     pixels_per_point: f32,
-) {
+) -> Vec<TextureId> {
+    let textures = shared.textures.borrow();
+    let render_textures = shared.render_textures.borrow();
+    let mut missing = Vec::new();
     let _ = window.set_active(true);
     unsafe {
         glu_sys::glEnable(glu_sys::GL_SCISSOR_TEST);
@@ -54,24 +83,38 @@ pub(super) fn draw(
             Primitive::Mesh(mesh) => mesh,
             _ => continue,
         };
-        let (tw, th, tex) = match mesh.texture_id {
-            TextureId::Managed(id) => {
-                let tex = &*textures[&TextureId::Managed(id)];
-                let (egui_tex_w, egui_tex_h) = (tex.size().x as f32, tex.size().y as f32);
-                (egui_tex_w, egui_tex_h, tex)
+        let found = match (textures.get(&mesh.texture_id), mesh.texture_id) {
+            (Some(tex), _) => Some(with_size(tex)),
+            (None, id) if render_textures.contains_key(&id) => {
+                Some(with_size(render_textures[&id].texture()))
+            }
+            (None, TextureId::User(id)) => user_tex_source
+                .as_deref_mut()
+                .map(|src| src.get_texture(id)),
+            (None, TextureId::Managed(_)) => None,
+        };
+        // An empty texture would make a mess of the UVs
+        let (tw, th, tex) = match found.filter(|(w, h, _)| *w > 0. && *h > 0.) {
+            Some(found) => found,
+            None => {
+                if !missing.contains(&mesh.texture_id) {
+                    missing.push(mesh.texture_id);
+                }
+                match shared.placeholder() {
+                    Some(tex) => with_size(tex),
+                    None => continue,
+                }
             }
-            TextureId::User(id) => user_tex_source.get_texture(id),
         };
         for idx in mesh.indices {
             let v = mesh.vertices[idx as usize];
             let sf_v = Vertex::new(
-                (v.pos.x, v.pos.y).into(),
+                (v.pos.x * pixels_per_point, v.pos.y * pixels_per_point).into(),
                 Color::rgba(v.color.r(), v.color.g(), v.color.b(), v.color.a()),
                 (v.uv.x * tw, v.uv.y * th).into(),
             );
             vertices.push(sf_v);
         }
-        let pixels_per_point = 1.;
         let win_size = window.size();
         let width_in_pixels = win_size.x;
         let height_in_pixels = win_size.y;
@@ -118,4 +161,23 @@ pub(super) fn draw(
         glu_sys::glDisable(glu_sys::GL_SCISSOR_TEST);
     }
     let _ = window.set_active(false);
+    missing
+}
+
+/// Captures the current contents of `window` into an egui image
+pub(super) fn capture(window: &RenderWindow) -> Result<ColorImage, TextureCreateError> {
+    let size = window.size();
+    let err = |_| TextureCreateError {
+        width: size.x as usize,
+        height: size.y as usize,
+    };
+    let mut tex = Texture::new().map_err(err)?;
+    tex.create(size.x, size.y).map_err(err)?;
+    // SAFETY: The texture was created with the same size as the window
+    unsafe {
+        tex.update_from_render_window(window, 0, 0);
+    }
+    let img = tex.copy_to_image().map_err(err)?;
+    // egui draws premultiplied colors, so the framebuffer holds them too
+    Ok(crate::image::to_color_image_premultiplied(&img))
 }
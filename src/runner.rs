@@ -0,0 +1,273 @@
+#[cfg(feature = "persistence")]
+use {
+    crate::persistence::{FileStorage, Storage, WindowGeometry},
+    std::{
+        path::PathBuf,
+        time::{Duration, Instant},
+    },
+};
+use {
+    crate::{BuildError, EventResult, PassError, SfEgui, SfEguiBuilder, UserTexSource},
+    egui::Context,
+    sfml::{
+        graphics::{Color, FloatRect, RenderTarget as _, RenderWindow, View},
+        window::{ContextSettings, Event, Style},
+        SfError,
+    },
+};
+
+/// An egui application that can be run with [`run_app`].
+pub trait App {
+    /// Called once before the first pass, after the persisted state was restored.
+    ///
+    /// This is the place to register textures, create [`SfmlViewport`](crate::SfmlViewport)s,
+    /// or install loaders and fonts.
+    fn setup(&mut self, _sf_egui: &mut SfEgui, _rw: &mut RenderWindow) {}
+    /// Show the ui. Called once per pass.
+    fn update(&mut self, ctx: &Context, frame: &mut Frame);
+    /// Called for every SFML event, after it was given to egui.
+    ///
+    /// `result` tells whether egui is interested in the event.
+    fn on_event(&mut self, _event: &Event, _result: EventResult) {}
+    /// Draw SFML content below the ui.
+    ///
+    /// Called after the window was cleared. `sf_egui` gives access to the textures of egui,
+    /// see [`SfEgui::managed_texture`].
+    fn draw_before_ui(&mut self, _rw: &mut RenderWindow, _sf_egui: &mut SfEgui) {}
+    /// Draw SFML content on top of the ui.
+    fn draw_after_ui(&mut self, _rw: &mut RenderWindow, _sf_egui: &mut SfEgui) {}
+    /// The source for the user textures that weren't registered with
+    /// [`SfEgui::register_texture`], see [`SfEgui::draw`].
+    fn user_textures(&mut self) -> Option<&mut dyn UserTexSource> {
+        None
+    }
+    /// Called once after the window was closed.
+    fn on_exit(&mut self) {}
+    /// Restore the app state saved with [`App::save`].
+    ///
+    /// Called once before the first pass, if [`WindowOptions::persistence_path`] is set.
+    #[cfg(feature = "persistence")]
+    fn load(&mut self, _storage: &dyn Storage) {}
+    /// Save the app state.
+    ///
+    /// Called on exit and every [`WindowOptions::autosave_interval`],
+    /// if [`WindowOptions::persistence_path`] is set.
+    #[cfg(feature = "persistence")]
+    fn save(&mut self, _storage: &mut dyn Storage) {}
+}
+
+/// Gives access to the window from [`App::update`]
+pub struct Frame<'a> {
+    window: &'a mut RenderWindow,
+}
+
+impl Frame<'_> {
+    /// The window the app runs in
+    pub fn window(&mut self) -> &mut RenderWindow {
+        self.window
+    }
+    /// Close the window, which ends the app after this pass
+    pub fn close(&mut self) {
+        self.window.close();
+    }
+}
+
+/// Options for the window created by [`run_app`]
+#[derive(Debug)]
+pub struct WindowOptions {
+    /// Title of the window
+    pub title: String,
+    /// Inner size of the window
+    pub size: (u32, u32),
+    /// Style of the window
+    pub style: Style,
+    /// OpenGL context settings of the window
+    pub context_settings: ContextSettings,
+    /// Whether to enable vertical sync
+    pub vsync: bool,
+    /// The color the window is cleared with before drawing
+    pub clear_color: Color,
+    /// Only repaint when there's new input, or egui asks for it.
+    ///
+    /// Turn this off if your app needs to update continuously, like a game.
+    pub reactive: bool,
+    /// Configuration of the [`SfEgui`] the app runs with.
+    ///
+    /// The window title and style are taken from these options.
+    pub sf_egui: SfEguiBuilder,
+    /// Where to persist the egui memory, the window geometry and the app state.
+    ///
+    /// Nothing is persisted if this is `None`.
+    #[cfg(feature = "persistence")]
+    pub persistence_path: Option<PathBuf>,
+    /// How often to save the persisted state, besides on exit
+    #[cfg(feature = "persistence")]
+    pub autosave_interval: Duration,
+}
+
+impl Default for WindowOptions {
+    fn default() -> Self {
+        Self {
+            title: "egui-sfml app".into(),
+            size: (800, 600),
+            style: Style::DEFAULT,
+            context_settings: ContextSettings::default(),
+            vsync: true,
+            clear_color: Color::BLACK,
+            reactive: true,
+            sf_egui: SfEguiBuilder::new(),
+            #[cfg(feature = "persistence")]
+            persistence_path: None,
+            #[cfg(feature = "persistence")]
+            autosave_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Error that can happen while running an app with [`run_app`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum RunError {
+    /// Failed to create the window
+    WindowCreate(SfError),
+    /// [`WindowOptions::sf_egui`] is configured wrong
+    Build(BuildError),
+    /// An egui pass failed
+    Pass(PassError),
+}
+
+impl From<BuildError> for RunError {
+    fn from(src: BuildError) -> Self {
+        Self::Build(src)
+    }
+}
+
+impl From<PassError> for RunError {
+    fn from(src: PassError) -> Self {
+        Self::Pass(src)
+    }
+}
+
+impl std::fmt::Display for RunError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RunError::WindowCreate(e) => f.write_fmt(format_args!("Failed to create window: {e}")),
+            RunError::Build(e) => f.write_fmt(format_args!("Invalid configuration: {e}")),
+            RunError::Pass(e) => f.write_fmt(format_args!("Pass error: {e}")),
+        }
+    }
+}
+
+impl std::error::Error for RunError {}
+
+/// Create a window and run `app` in it until the window is closed.
+///
+/// This takes care of the event loop, closing the window on close requests
+/// (unless [`App::update`] cancels them with [`egui::ViewportCommand::CancelClose`]),
+/// keeping the view in sync with the window size, and only repainting when needed
+/// (see [`WindowOptions::reactive`]).
+pub fn run_app(options: WindowOptions, mut app: impl App) -> Result<(), RunError> {
+    #[cfg(feature = "persistence")]
+    let mut storage = options
+        .persistence_path
+        .as_ref()
+        .map(FileStorage::from_path);
+    #[cfg(feature = "persistence")]
+    let mut geometry = storage
+        .as_ref()
+        .and_then(|storage| WindowGeometry::load(storage));
+    #[cfg(feature = "persistence")]
+    let size = geometry.map_or(options.size, |geom| geom.size);
+    #[cfg(not(feature = "persistence"))]
+    let size = options.size;
+    let mut rw = RenderWindow::new(
+        size,
+        options.title.as_str(),
+        options.style,
+        &options.context_settings,
+    )
+    .map_err(RunError::WindowCreate)?;
+    rw.set_vertical_sync_enabled(options.vsync);
+    let mut sf_egui = options
+        .sf_egui
+        .window_attributes(options.title.as_str(), options.style)
+        .build(&rw)?;
+    #[cfg(feature = "persistence")]
+    let mut last_save = Instant::now();
+    #[cfg(feature = "persistence")]
+    if let Some(storage) = &storage {
+        if let Some(geom) = geometry {
+            rw.set_position(geom.position.into());
+        }
+        sf_egui.load_memory(storage);
+        app.load(storage);
+    }
+    app.setup(&mut sf_egui, &mut rw);
+    while rw.is_open() {
+        let events = if options.reactive {
+            sf_egui.wait_events(&mut rw)
+        } else {
+            let events: Vec<Event> = std::iter::from_fn(|| rw.poll_event()).collect();
+            for event in &events {
+                sf_egui.add_event(event);
+            }
+            events
+        };
+        for event in &events {
+            if let Event::Resized { width, height } = *event {
+                let rect = FloatRect::new(0., 0., width as f32, height as f32);
+                if let Ok(view) = View::from_rect(rect) {
+                    rw.set_view(&view);
+                }
+            }
+            app.on_event(event, sf_egui.event_result(event));
+        }
+        // Closing is up to the ui, which can cancel it with `ViewportCommand::CancelClose`
+        let di = sf_egui.run(&mut rw, |rw, ctx| {
+            app.update(ctx, &mut Frame { window: rw });
+        })?;
+        if sf_egui.close_requested() {
+            rw.close();
+        }
+        if !rw.is_open() {
+            break;
+        }
+        rw.clear(options.clear_color);
+        app.draw_before_ui(&mut rw, &mut sf_egui);
+        sf_egui.draw(di, &mut rw, app.user_textures());
+        app.draw_after_ui(&mut rw, &mut sf_egui);
+        rw.display();
+        #[cfg(feature = "persistence")]
+        if let Some(storage) = &mut storage {
+            // The geometry can't be queried after the window is closed, so keep it around
+            if rw.is_open() {
+                geometry = Some(WindowGeometry::of(&rw));
+            }
+            if last_save.elapsed() >= options.autosave_interval {
+                save(storage, &sf_egui, &mut app, geometry);
+                last_save = Instant::now();
+            }
+        }
+    }
+    #[cfg(feature = "persistence")]
+    if let Some(storage) = &mut storage {
+        save(storage, &sf_egui, &mut app, geometry);
+    }
+    app.on_exit();
+    Ok(())
+}
+
+#[cfg(feature = "persistence")]
+fn save(
+    storage: &mut FileStorage,
+    sf_egui: &SfEgui,
+    app: &mut impl App,
+    geometry: Option<WindowGeometry>,
+) {
+    sf_egui.save_memory(storage);
+    if let Some(geom) = geometry {
+        geom.save(storage);
+    }
+    app.save(storage);
+    storage.flush();
+}
@@ -0,0 +1,92 @@
+use {
+    crate::{SfEgui, Shared},
+    egui::{Color32, Rect, Response, Sense, TextureId, Ui, Vec2},
+    sfml::{
+        graphics::{RenderTarget as _, RenderTexture},
+        system::Vector2f,
+    },
+    std::rc::Rc,
+};
+
+/// An egui widget showing SFML content, drawn into an offscreen texture.
+///
+/// Create it once with [`SfEgui::sfml_viewport`], and show it every pass with
+/// [`SfmlViewport::show`].
+pub struct SfmlViewport {
+    shared: Rc<Shared>,
+    id: TextureId,
+}
+
+impl SfmlViewport {
+    /// The texture id the content is shown with
+    pub fn texture_id(&self) -> TextureId {
+        self.id
+    }
+    /// Allocate `size` points in `ui`, and show what `draw` draws there.
+    ///
+    /// The render texture is resized to match the widget before `draw` is called.
+    /// `draw` also gets the position of the pointer in the pixel coordinates of the
+    /// render texture, if the pointer is over the widget.
+    ///
+    /// The content is blended as premultiplied alpha, so clear the render texture with an
+    /// opaque color.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        size: Vec2,
+        draw: impl FnOnce(&mut RenderTexture, Option<Vector2f>),
+    ) -> Response {
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+        let ppp = ui.ctx().pixels_per_point();
+        let width = (rect.width() * ppp).round().max(1.) as u32;
+        let height = (rect.height() * ppp).round().max(1.) as u32;
+        let mut render_textures = self.shared.render_textures.borrow_mut();
+        let outdated = render_textures
+            .get(&self.id)
+            .is_none_or(|rt| rt.size() != (width, height).into());
+        if outdated {
+            match RenderTexture::new(width, height) {
+                Ok(rt) => {
+                    render_textures.insert(self.id, rt);
+                }
+                Err(e) => {
+                    eprintln!("egui_sfml: Failed to create {width}x{height} render texture: {e}");
+                    render_textures.remove(&self.id);
+                    return response;
+                }
+            }
+        }
+        let Some(rt) = render_textures.get_mut(&self.id) else {
+            return response;
+        };
+        let pointer = response.hover_pos().map(|pos| {
+            let local = (pos - rect.min) * ppp;
+            Vector2f::new(local.x, local.y)
+        });
+        draw(rt, pointer);
+        rt.display();
+        if ui.is_rect_visible(rect) {
+            let uv = Rect::from_min_max([0., 0.].into(), [1., 1.].into());
+            ui.painter().image(self.id, rect, uv, Color32::WHITE);
+        }
+        response
+    }
+}
+
+impl Drop for SfmlViewport {
+    fn drop(&mut self) {
+        self.shared.render_textures.borrow_mut().remove(&self.id);
+    }
+}
+
+impl SfEgui {
+    /// Create a widget that shows SFML content in the ui, see [`SfmlViewport`]
+    pub fn sfml_viewport(&mut self) -> SfmlViewport {
+        let id = TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        SfmlViewport {
+            shared: self.shared.clone(),
+            id,
+        }
+    }
+}
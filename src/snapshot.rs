@@ -0,0 +1,517 @@
+//! Rendering egui output on the CPU, for snapshot tests on machines without a GPU
+//!
+//! [`rasterize`] draws tessellated egui primitives the same way [`SfEgui::draw`] does:
+//! same clipping, nearest-neighbor texture sampling, and blend mode.
+//! [`check_snapshot`] compares the result against a reference image on disk.
+//!
+//! The pixels of the images hold the raw values that end up in the framebuffer,
+//! which are premultiplied by alpha.
+
+use {
+    crate::{
+        image::{from_color_image_premultiplied, to_color_image_premultiplied},
+        SfEgui, TextureMap, Window,
+    },
+    egui::{
+        epaint::{ClippedPrimitive, Primitive, Vertex},
+        Color32, ColorImage, ImageData, Pos2, TextureId, TexturesDelta,
+    },
+    sfml::graphics::Image,
+    std::{
+        collections::HashMap,
+        path::{Path, PathBuf},
+    },
+};
+
+/// Textures available to [`rasterize`], kept on the CPU
+#[derive(Debug, Clone, Default)]
+pub struct SnapshotTextures {
+    images: HashMap<TextureId, ColorImage>,
+}
+
+impl SnapshotTextures {
+    /// Apply the texture changes egui outputs at the end of a pass
+    pub fn update(&mut self, delta: &TexturesDelta) {
+        for (id, delta) in &delta.set {
+            let ImageData::Color(image) = &delta.image;
+            match delta.pos {
+                None => {
+                    self.images.insert(*id, (**image).clone());
+                }
+                Some([x, y]) => {
+                    let Some(target) = self.images.get_mut(id) else {
+                        eprintln!("egui_sfml: Partial update of unknown texture {id:?}");
+                        continue;
+                    };
+                    let [w, h] = image.size;
+                    for row in 0..h.min(target.height().saturating_sub(y)) {
+                        for col in 0..w.min(target.width().saturating_sub(x)) {
+                            target[(x + col, y + row)] = image[(col, row)];
+                        }
+                    }
+                }
+            }
+        }
+        for id in &delta.free {
+            self.images.remove(id);
+        }
+    }
+    /// Add a texture, for example for a [`TextureId::User`] id
+    pub fn insert(&mut self, id: TextureId, image: ColorImage) {
+        self.images.insert(id, image);
+    }
+    /// Get the texture for `id`
+    pub fn get(&self, id: TextureId) -> Option<&ColorImage> {
+        self.images.get(&id)
+    }
+    /// Download the managed textures of `SfEgui` from the GPU
+    fn from_texture_map(textures: &TextureMap) -> Self {
+        let images = textures
+            .iter()
+            .filter_map(|(id, tex)| {
+                let image = tex.copy_to_image().ok()?;
+                Some((*id, to_color_image_premultiplied(&image)))
+            })
+            .collect();
+        Self { images }
+    }
+}
+
+/// Draw `primitives` onto `target`.
+///
+/// `primitives` are in points, which are scaled by `pixels_per_point` to the pixels of `target`.
+/// Meshes whose texture is not in `textures` are skipped.
+pub fn rasterize(
+    target: &mut ColorImage,
+    primitives: &[ClippedPrimitive],
+    textures: &SnapshotTextures,
+    pixels_per_point: f32,
+) {
+    let [width, height] = target.size;
+    for ClippedPrimitive {
+        clip_rect,
+        primitive,
+    } in primitives
+    {
+        let Primitive::Mesh(mesh) = primitive else {
+            continue;
+        };
+        let Some(texture) = textures.get(mesh.texture_id) else {
+            eprintln!(
+                "egui_sfml: Snapshot of unknown texture {:?}",
+                mesh.texture_id
+            );
+            continue;
+        };
+        // Same clip rect transformation as `rendering::draw`
+        let clip_rect = *clip_rect * pixels_per_point;
+        let clip_min_x = clip_rect.min.x.clamp(0.0, width as f32);
+        let clip_min_y = clip_rect.min.y.clamp(0.0, height as f32);
+        let clip_max_x = clip_rect.max.x.clamp(clip_min_x, width as f32);
+        let clip_max_y = clip_rect.max.y.clamp(clip_min_y, height as f32);
+        let clip = [
+            clip_min_x.round() as usize,
+            clip_min_y.round() as usize,
+            clip_max_x.round() as usize,
+            clip_max_y.round() as usize,
+        ];
+        for tri in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| {
+                let mut v = mesh.vertices[tri[i] as usize];
+                v.pos *= pixels_per_point;
+                v
+            });
+            draw_triangle(target, clip, texture, a, b, c);
+        }
+    }
+}
+
+/// The edge function of the edge `a` → `b` at `p`, positive on one side and negative on the other
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Whether pixels exactly on the edge `a` → `b` belong to the triangle.
+///
+/// Of two triangles sharing an edge, exactly one gets the pixels on it, so they are not
+/// blended twice.
+fn owns_edge(a: Pos2, b: Pos2) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    dy > 0.0 || (dy == 0.0 && dx < 0.0)
+}
+
+fn draw_triangle(
+    target: &mut ColorImage,
+    [clip_min_x, clip_min_y, clip_max_x, clip_max_y]: [usize; 4],
+    texture: &ColorImage,
+    a: Vertex,
+    mut b: Vertex,
+    mut c: Vertex,
+) {
+    let mut area = edge(a.pos, b.pos, c.pos);
+    if area == 0.0 {
+        return;
+    }
+    if area < 0.0 {
+        std::mem::swap(&mut b, &mut c);
+        area = -area;
+    }
+    let xs = span([a.pos.x, b.pos.x, c.pos.x], clip_min_x, clip_max_x);
+    let ys = span([a.pos.y, b.pos.y, c.pos.y], clip_min_y, clip_max_y);
+    let edges = [(b, c), (c, a), (a, b)];
+    for y in ys {
+        for x in xs.clone() {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            let mut weights = [0.0; 3];
+            let mut inside = true;
+            for (w, (from, to)) in weights.iter_mut().zip(edges) {
+                *w = edge(from.pos, to.pos, p);
+                inside &= *w > 0.0 || (*w == 0.0 && owns_edge(from.pos, to.pos));
+            }
+            if !inside {
+                continue;
+            }
+            let [wa, wb, wc] = weights.map(|w| w / area);
+            let u = wa * a.uv.x + wb * b.uv.x + wc * c.uv.x;
+            let v = wa * a.uv.y + wb * b.uv.y + wc * c.uv.y;
+            let texel = sample(texture, u, v);
+            let mut src = [0.0; 4];
+            for (i, s) in src.iter_mut().enumerate() {
+                let color =
+                    wa * channel(a.color, i) + wb * channel(b.color, i) + wc * channel(c.color, i);
+                *s = color * channel(texel, i);
+            }
+            let dst = &mut target[(x, y)];
+            *dst = blend(src, *dst);
+        }
+    }
+}
+
+/// The pixels covered by `coords`, limited to `min..max`
+fn span(coords: [f32; 3], min: usize, max: usize) -> std::ops::Range<usize> {
+    let lo = coords.into_iter().fold(f32::INFINITY, f32::min).floor();
+    let hi = coords.into_iter().fold(f32::NEG_INFINITY, f32::max).ceil();
+    (lo.max(min as f32) as usize)..(hi.min(max as f32) as usize)
+}
+
+/// Channel `i` of `color`, from 0 to 1
+fn channel(color: Color32, i: usize) -> f32 {
+    color.to_array()[i] as f32 / 255.0
+}
+
+/// Nearest-neighbor sampling, like SFML does for textures that are not smooth
+fn sample(texture: &ColorImage, u: f32, v: f32) -> Color32 {
+    let [w, h] = texture.size;
+    if w == 0 || h == 0 {
+        return Color32::TRANSPARENT;
+    }
+    let x = ((u * w as f32).floor().max(0.0) as usize).min(w - 1);
+    let y = ((v * h as f32).floor().max(0.0) as usize).min(h - 1);
+    texture[(x, y)]
+}
+
+/// The blend mode of `rendering::draw`: premultiplied alpha for the color,
+/// and "under" blending for the alpha
+fn blend(src: [f32; 4], dst: Color32) -> Color32 {
+    let dst = [0, 1, 2, 3].map(|i| channel(dst, i));
+    let [r, g, b] = [0, 1, 2].map(|i| src[i] + dst[i] * (1.0 - src[3]));
+    let a = src[3] * (1.0 - dst[3]) + dst[3];
+    let [r, g, b, a] = [r, g, b, a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    Color32::from_rgba_premultiplied(r, g, b, a)
+}
+
+/// Compare two images channel by channel.
+///
+/// Returns the number of pixels that differ by more than `tolerance` in any channel,
+/// and an image where those pixels are red, and the others are a faded copy of `expected`.
+pub fn diff(
+    actual: &ColorImage,
+    expected: &ColorImage,
+    tolerance: u8,
+) -> Result<(usize, ColorImage), SnapshotError> {
+    if actual.size != expected.size {
+        return Err(SnapshotError::SizeMismatch {
+            expected: expected.size,
+            actual: actual.size,
+        });
+    }
+    let mut count = 0;
+    let pixels = actual
+        .pixels
+        .iter()
+        .zip(&expected.pixels)
+        .map(|(a, e)| {
+            let differs = a
+                .to_array()
+                .iter()
+                .zip(e.to_array())
+                .any(|(a, e)| a.abs_diff(e) > tolerance);
+            if differs {
+                count += 1;
+                Color32::RED
+            } else {
+                let [r, g, b, _] = e.to_array();
+                Color32::from_rgb(r / 4, g / 4, b / 4)
+            }
+        })
+        .collect();
+    Ok((count, ColorImage::new(expected.size, pixels)))
+}
+
+/// Set this environment variable to save the checked images as the new references
+pub const UPDATE_SNAPSHOTS_VAR: &str = "UPDATE_SNAPSHOTS";
+
+/// Compare `image` with the reference image at `path`.
+///
+/// On mismatch, or if there is no reference image, `image` is saved next to the reference
+/// with the extension `new.png`, and the differences with the extension `diff.png`.
+///
+/// If the environment variable [`UPDATE_SNAPSHOTS_VAR`] is set, `image` is saved as the
+/// reference instead, and the check passes.
+pub fn check_snapshot(
+    image: &ColorImage,
+    path: impl AsRef<Path>,
+    tolerance: u8,
+) -> Result<(), SnapshotError> {
+    let path = path.as_ref();
+    if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+        return save_image(image, path);
+    }
+    if !path.exists() {
+        save_image(image, &path.with_extension("new.png"))?;
+        return Err(SnapshotError::MissingReference(path.to_owned()));
+    }
+    let expected = load_image(path)?;
+    if expected.size != image.size {
+        save_image(image, &path.with_extension("new.png"))?;
+        return Err(SnapshotError::SizeMismatch {
+            expected: expected.size,
+            actual: image.size,
+        });
+    }
+    let (differing_pixels, diff_image) = diff(image, &expected, tolerance)?;
+    if differing_pixels == 0 {
+        return Ok(());
+    }
+    let diff_path = path.with_extension("diff.png");
+    save_image(image, &path.with_extension("new.png"))?;
+    save_image(&diff_image, &diff_path)?;
+    Err(SnapshotError::Mismatch {
+        differing_pixels,
+        diff_path,
+    })
+}
+
+/// Load an image saved with [`save_image`]
+pub fn load_image(path: &Path) -> Result<ColorImage, SnapshotError> {
+    let err = || SnapshotError::Load(path.to_owned());
+    let image = Image::from_file(path.to_str().ok_or_else(err)?).map_err(|_| err())?;
+    Ok(to_color_image_premultiplied(&image))
+}
+
+/// Save the raw pixel values of `image` to a file.
+///
+/// The format is determined by the extension, PNG is recommended because it's lossless.
+pub fn save_image(image: &ColorImage, path: &Path) -> Result<(), SnapshotError> {
+    let err = || SnapshotError::Save(path.to_owned());
+    from_color_image_premultiplied(image)
+        .map_err(|_| err())?
+        .save_to_file(path.to_str().ok_or_else(err)?)
+        .map_err(|_| err())
+}
+
+/// Error when checking a snapshot with [`check_snapshot`]
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum SnapshotError {
+    /// Failed to load the image at this path
+    Load(PathBuf),
+    /// Failed to save the image at this path
+    Save(PathBuf),
+    /// There is no reference image at this path
+    MissingReference(PathBuf),
+    /// The image doesn't have the size of the reference image
+    SizeMismatch {
+        /// Size of the reference image
+        expected: [usize; 2],
+        /// Size of the image being checked
+        actual: [usize; 2],
+    },
+    /// Some pixels differ from the reference image by more than the tolerance
+    Mismatch {
+        /// The number of pixels that differ
+        differing_pixels: usize,
+        /// Where the image showing the differences was saved
+        diff_path: PathBuf,
+    },
+}
+
+impl std::fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Load(path) => {
+                f.write_fmt(format_args!("Failed to load {}", path.display()))
+            }
+            SnapshotError::Save(path) => {
+                f.write_fmt(format_args!("Failed to save {}", path.display()))
+            }
+            SnapshotError::MissingReference(path) => f.write_fmt(format_args!(
+                "No reference image at {}, set {UPDATE_SNAPSHOTS_VAR} to create it",
+                path.display()
+            )),
+            SnapshotError::SizeMismatch { expected, actual } => f.write_fmt(format_args!(
+                "Snapshot is {}x{}, expected {}x{}",
+                actual[0], actual[1], expected[0], expected[1]
+            )),
+            SnapshotError::Mismatch {
+                differing_pixels,
+                diff_path,
+            } => f.write_fmt(format_args!(
+                "{differing_pixels} pixels differ, see {}",
+                diff_path.display()
+            )),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl SfEgui {
+    /// Render `input` on the CPU instead of drawing it to a window.
+    ///
+    /// The managed textures are downloaded from the GPU, user textures are not available.
+    pub fn snapshot(&self, input: crate::DrawInput, window: &dyn Window) -> ColorImage {
+        let textures = SnapshotTextures::from_texture_map(&self.shared.textures.borrow());
+        let size = window.size();
+        let mut image = ColorImage::new(
+            [size.x as usize, size.y as usize],
+            vec![Color32::TRANSPARENT; size.x as usize * size.y as usize],
+        );
+        let primitives = self.ctx.tessellate(input.shapes, input.pixels_per_point);
+        rasterize(&mut image, &primitives, &textures, input.pixels_per_point);
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        egui::{epaint::Mesh, Rect},
+    };
+
+    const BACKGROUND: Color32 = Color32::BLUE;
+    /// Red at half opacity, premultiplied
+    const HALF_RED: Color32 = Color32::from_rgba_premultiplied(128, 0, 0, 128);
+
+    fn white_texture() -> SnapshotTextures {
+        let mut textures = SnapshotTextures::default();
+        textures.insert(
+            TextureId::Managed(0),
+            ColorImage::new([1, 1], vec![Color32::WHITE]),
+        );
+        textures
+    }
+
+    /// A half transparent red quad from (1, 1) to (5, 5), clipped at x = 3
+    fn clipped_quad() -> Vec<ClippedPrimitive> {
+        let mut mesh = Mesh::with_texture(TextureId::Managed(0));
+        mesh.add_rect_with_uv(
+            Rect::from_min_max(Pos2::new(1., 1.), Pos2::new(5., 5.)),
+            Rect::from_min_max(Pos2::ZERO, Pos2::new(1., 1.)),
+            HALF_RED,
+        );
+        vec![ClippedPrimitive {
+            clip_rect: Rect::from_min_max(Pos2::ZERO, Pos2::new(3., 8.)),
+            primitive: Primitive::Mesh(mesh),
+        }]
+    }
+
+    fn rasterized(size: usize, pixels_per_point: f32) -> ColorImage {
+        let mut image = ColorImage::new([size, size], vec![BACKGROUND; size * size]);
+        rasterize(
+            &mut image,
+            &clipped_quad(),
+            &white_texture(),
+            pixels_per_point,
+        );
+        image
+    }
+
+    #[test]
+    fn rasterize_blends_and_clips() {
+        let image = rasterized(8, 1.0);
+        // Premultiplied color over the background, and the alpha of the background kept
+        let blended = Color32::from_rgba_premultiplied(128, 0, 127, 255);
+        for y in 0..8 {
+            for x in 0..8 {
+                let expected = if (1..3).contains(&x) && (1..5).contains(&y) {
+                    blended
+                } else {
+                    BACKGROUND
+                };
+                assert_eq!(image[(x, y)], expected, "pixel ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn rasterize_scales_by_pixels_per_point() {
+        let image = rasterized(16, 2.0);
+        let blended = Color32::from_rgba_premultiplied(128, 0, 127, 255);
+        assert_eq!(image[(2, 2)], blended);
+        assert_eq!(image[(5, 9)], blended);
+        assert_eq!(image[(1, 1)], BACKGROUND);
+        assert_eq!(image[(6, 4)], BACKGROUND, "clipped");
+        assert_eq!(image[(4, 10)], BACKGROUND, "below the quad");
+    }
+
+    #[test]
+    fn blend_over_transparent_keeps_source() {
+        let mut image = ColorImage::new([8, 8], vec![Color32::TRANSPARENT; 64]);
+        rasterize(&mut image, &clipped_quad(), &white_texture(), 1.0);
+        assert_eq!(image[(1, 1)], HALF_RED);
+    }
+
+    #[test]
+    fn diff_counts_differing_pixels() {
+        let expected = rasterized(8, 1.0);
+        let mut actual = expected.clone();
+        actual[(0, 0)] = Color32::from_rgb(0, 0, 250);
+        actual[(7, 7)] = Color32::WHITE;
+        let (count, image) = diff(&actual, &expected, 5).unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(image.size, expected.size);
+        assert_eq!(image[(7, 7)], Color32::RED);
+        assert_ne!(image[(0, 0)], Color32::RED);
+    }
+
+    #[test]
+    fn diff_rejects_different_sizes() {
+        let small = ColorImage::new([2, 2], vec![BACKGROUND; 4]);
+        let large = ColorImage::new([2, 3], vec![BACKGROUND; 6]);
+        assert!(matches!(
+            diff(&small, &large, 0),
+            Err(SnapshotError::SizeMismatch {
+                expected: [2, 3],
+                actual: [2, 2],
+            })
+        ));
+    }
+
+    #[test]
+    fn missing_reference_is_an_error() {
+        if std::env::var_os(UPDATE_SNAPSHOTS_VAR).is_some() {
+            return;
+        }
+        let dir = std::env::temp_dir().join(format!("egui_sfml_snapshot_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("missing.png");
+        let image = ColorImage::new([2, 2], vec![BACKGROUND; 4]);
+        let result = check_snapshot(&image, &path, 0);
+        assert!(matches!(result, Err(SnapshotError::MissingReference(p)) if p == path));
+        assert!(!path.exists());
+        assert!(path.with_extension("new.png").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,597 @@
+//! Translation of SFML events to egui events
+//!
+//! This is the layer [`SfEgui::add_event`](crate::SfEgui::add_event) is built on.
+//! It is pure: it doesn't read the clipboard or the keyboard, so it can be used
+//! for custom input pipelines, like replays, networked input, or tests.
+//!
+//! Pointer positions are left in window pixels. [`SfEgui`](crate::SfEgui) converts them to
+//! egui points at the start of each pass.
+
+use {
+    egui::{PointerButton, Pos2},
+    sfml::window::{mouse, Event, Key},
+    smallvec::SmallVec,
+};
+
+/// The egui events an SFML event translates to
+pub type TranslatedEvents = SmallVec<[egui::Event; 2]>;
+
+/// The key used for shortcuts like copy and paste, reported to egui as
+/// [`egui::Modifiers::command`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandKey {
+    /// Control, the convention everywhere but macOS
+    Ctrl,
+    /// The system key, which is Cmd on macOS.
+    ///
+    /// Also reported as [`egui::Modifiers::mac_cmd`].
+    System,
+}
+
+impl CommandKey {
+    /// The convention of the platform this is compiled for
+    pub const fn native() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::System
+        } else {
+            Self::Ctrl
+        }
+    }
+}
+
+impl Default for CommandKey {
+    fn default() -> Self {
+        Self::native()
+    }
+}
+
+/// State that the translation of events depends on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TranslationState {
+    /// The modifier keys currently held down
+    pub modifiers: egui::Modifiers,
+    /// The key used for shortcuts. Defaults to the convention of the platform.
+    pub command_key: CommandKey,
+}
+
+impl TranslationState {
+    /// Keep the state up to date with `event`.
+    ///
+    /// Call this for every event before translating it. The modifiers are tracked
+    /// from the key events alone, so they are only as accurate as the event stream.
+    pub fn update(&mut self, event: &Event) {
+        match *event {
+            Event::KeyPressed {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+                scan: _,
+            } => self.update_modifiers(code, true, [alt, ctrl, shift, system]),
+            Event::KeyReleased {
+                code,
+                alt,
+                ctrl,
+                shift,
+                system,
+                scan: _,
+            } => self.update_modifiers(code, false, [alt, ctrl, shift, system]),
+            // Releases that happen while the window is unfocused are never seen
+            Event::LostFocus => self.modifiers = egui::Modifiers::NONE,
+            _ => {}
+        }
+    }
+    fn update_modifiers(&mut self, code: Key, pressed: bool, flags: [bool; 4]) {
+        let [mut alt, mut ctrl, mut shift, mut system] = flags;
+        // Depending on the platform, the flags of the event for a modifier key itself
+        // might reflect the state before the event, so trust the key code instead.
+        match code {
+            Key::LAlt | Key::RAlt => alt = pressed,
+            Key::LControl | Key::RControl => ctrl = pressed,
+            Key::LShift | Key::RShift => shift = pressed,
+            Key::LSystem | Key::RSystem => system = pressed,
+            _ => {}
+        }
+        self.modifiers = modifier(alt, ctrl, shift, system, self.command_key);
+    }
+    /// Read the modifiers from the keyboard.
+    ///
+    /// Unlike the rest of the translation layer, this reads global keyboard state.
+    /// Useful to resynchronize the modifiers when the window regains focus.
+    pub fn resync_from_keyboard(&mut self) {
+        self.modifiers = modifier(
+            Key::LAlt.is_pressed() || Key::RAlt.is_pressed(),
+            Key::LControl.is_pressed() || Key::RControl.is_pressed(),
+            Key::LShift.is_pressed() || Key::RShift.is_pressed(),
+            Key::LSystem.is_pressed() || Key::RSystem.is_pressed(),
+            self.command_key,
+        );
+    }
+}
+
+/// Translates an SFML event to egui events.
+///
+/// `state` should already be updated with `event`, see [`TranslationState::update`].
+///
+/// The paste shortcut translates to an [`egui::Event::Paste`] with empty text,
+/// because this doesn't read the clipboard. Fill in the clipboard contents before
+/// giving it to egui.
+///
+/// [`Event::Resized`] doesn't translate to any events, it should update
+/// [`egui::RawInput::screen_rect`] instead.
+pub fn translate_event(event: &Event, state: &TranslationState) -> TranslatedEvents {
+    let mut events = TranslatedEvents::new();
+    match *event {
+        Event::KeyPressed {
+            code,
+            alt,
+            ctrl,
+            shift,
+            system,
+            scan: _,
+        } => {
+            let modifiers = modifier(alt, ctrl, shift, system, state.command_key);
+            if modifiers.command {
+                match code {
+                    Key::V => events.push(egui::Event::Paste(String::new())),
+                    Key::C => events.push(egui::Event::Copy),
+                    Key::X => events.push(egui::Event::Cut),
+                    _ => {}
+                }
+            }
+            if let Some(key) = key_conv(code) {
+                events.push(egui::Event::Key {
+                    key,
+                    modifiers,
+                    pressed: true,
+                    repeat: false,
+                    physical_key: None,
+                });
+            }
+        }
+        Event::KeyReleased {
+            code,
+            alt,
+            ctrl,
+            shift,
+            system,
+            scan: _,
+        } => {
+            if let Some(key) = key_conv(code) {
+                events.push(egui::Event::Key {
+                    key,
+                    modifiers: modifier(alt, ctrl, shift, system, state.command_key),
+                    pressed: false,
+                    repeat: false,
+                    physical_key: None,
+                });
+            }
+        }
+        Event::MouseMoved { x, y } => {
+            events.push(egui::Event::PointerMoved(Pos2::new(x as f32, y as f32)));
+        }
+        Event::MouseButtonPressed { x, y, button } => {
+            if let Some(button) = button_conv(button) {
+                events.push(egui::Event::PointerButton {
+                    pos: Pos2::new(x as f32, y as f32),
+                    button,
+                    pressed: true,
+                    modifiers: state.modifiers,
+                });
+            }
+        }
+        Event::MouseButtonReleased { x, y, button } => {
+            if let Some(button) = button_conv(button) {
+                events.push(egui::Event::PointerButton {
+                    pos: Pos2::new(x as f32, y as f32),
+                    button,
+                    pressed: false,
+                    modifiers: state.modifiers,
+                });
+            }
+        }
+        Event::TextEntered { unicode } if !unicode.is_control() => {
+            events.push(egui::Event::Text(unicode.to_string()));
+        }
+        Event::MouseWheelScrolled { delta, .. } if state.modifiers.command => {
+            events.push(egui::Event::Zoom(if delta > 0.0 { 1.1 } else { 0.9 }));
+        }
+        _ => {}
+    }
+    events
+}
+
+/// Converts an SFML mouse button to an egui pointer button
+pub fn button_conv(button: mouse::Button) -> Option<PointerButton> {
+    let but = match button {
+        mouse::Button::Left => PointerButton::Primary,
+        mouse::Button::Right => PointerButton::Secondary,
+        mouse::Button::Middle => PointerButton::Middle,
+        _ => return None,
+    };
+    Some(but)
+}
+
+/// Converts an SFML key to an egui key
+pub fn key_conv(code: Key) -> Option<egui::Key> {
+    use egui::Key as EKey;
+    Some(match code {
+        Key::Down => EKey::ArrowDown,
+        Key::Left => EKey::ArrowLeft,
+        Key::Right => EKey::ArrowRight,
+        Key::Up => EKey::ArrowUp,
+        Key::Escape => EKey::Escape,
+        Key::Tab => EKey::Tab,
+        Key::Backspace => EKey::Backspace,
+        Key::Enter => EKey::Enter,
+        Key::Space => EKey::Space,
+        Key::Insert => EKey::Insert,
+        Key::Delete => EKey::Delete,
+        Key::Home => EKey::Home,
+        Key::End => EKey::End,
+        Key::PageUp => EKey::PageUp,
+        Key::PageDown => EKey::PageDown,
+        Key::LBracket => EKey::OpenBracket,
+        Key::RBracket => EKey::CloseBracket,
+        Key::Num0 => EKey::Num0,
+        Key::Num1 => EKey::Num1,
+        Key::Num2 => EKey::Num2,
+        Key::Num3 => EKey::Num3,
+        Key::Num4 => EKey::Num4,
+        Key::Num5 => EKey::Num5,
+        Key::Num6 => EKey::Num6,
+        Key::Num7 => EKey::Num7,
+        Key::Num8 => EKey::Num8,
+        Key::Num9 => EKey::Num9,
+        Key::A => EKey::A,
+        Key::B => EKey::B,
+        Key::C => EKey::C,
+        Key::D => EKey::D,
+        Key::E => EKey::E,
+        Key::F => EKey::F,
+        Key::G => EKey::G,
+        Key::H => EKey::H,
+        Key::I => EKey::I,
+        Key::J => EKey::J,
+        Key::K => EKey::K,
+        Key::L => EKey::L,
+        Key::M => EKey::M,
+        Key::N => EKey::N,
+        Key::O => EKey::O,
+        Key::P => EKey::P,
+        Key::Q => EKey::Q,
+        Key::R => EKey::R,
+        Key::S => EKey::S,
+        Key::T => EKey::T,
+        Key::U => EKey::U,
+        Key::V => EKey::V,
+        Key::W => EKey::W,
+        Key::X => EKey::X,
+        Key::Y => EKey::Y,
+        Key::Z => EKey::Z,
+        Key::F1 => EKey::F1,
+        Key::F2 => EKey::F2,
+        Key::F3 => EKey::F3,
+        Key::F4 => EKey::F4,
+        Key::F5 => EKey::F5,
+        Key::F6 => EKey::F6,
+        Key::F7 => EKey::F7,
+        Key::F8 => EKey::F8,
+        Key::F9 => EKey::F9,
+        Key::F10 => EKey::F10,
+        Key::F11 => EKey::F11,
+        Key::F12 => EKey::F12,
+        Key::Equal => EKey::Equals,
+        Key::Hyphen => EKey::Minus,
+        Key::Slash => EKey::Slash,
+        Key::Tilde => EKey::Backtick,
+        _ => return None,
+    })
+}
+
+/// Creates egui modifiers from the modifier flags of SFML key events
+pub fn modifier(
+    alt: bool,
+    ctrl: bool,
+    shift: bool,
+    system: bool,
+    command_key: CommandKey,
+) -> egui::Modifiers {
+    let command = match command_key {
+        CommandKey::Ctrl => ctrl,
+        CommandKey::System => system,
+    };
+    egui::Modifiers {
+        alt,
+        ctrl,
+        shift,
+        command,
+        mac_cmd: command_key == CommandKey::System && system,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        egui::{Key as EKey, Modifiers},
+        sfml::window::Scancode,
+    };
+
+    const NO_MODS: [bool; 4] = [false; 4];
+    const CTRL: [bool; 4] = [false, true, false, false];
+    const SYSTEM: [bool; 4] = [false, false, false, true];
+
+    fn key_event(code: Key, pressed: bool, [alt, ctrl, shift, system]: [bool; 4]) -> Event {
+        if pressed {
+            Event::KeyPressed {
+                code,
+                scan: Scancode::Unknown,
+                alt,
+                ctrl,
+                shift,
+                system,
+            }
+        } else {
+            Event::KeyReleased {
+                code,
+                scan: Scancode::Unknown,
+                alt,
+                ctrl,
+                shift,
+                system,
+            }
+        }
+    }
+
+    fn new_state(command_key: CommandKey) -> TranslationState {
+        TranslationState {
+            modifiers: Modifiers::NONE,
+            command_key,
+        }
+    }
+
+    /// Updates `state` with `event` and translates it, like `SfEgui::add_event` does
+    fn translate(state: &mut TranslationState, event: Event) -> Vec<egui::Event> {
+        state.update(&event);
+        translate_event(&event, state).into_vec()
+    }
+
+    #[test]
+    fn key_conv_maps_every_key() {
+        let mapped = [
+            (Key::Down, EKey::ArrowDown),
+            (Key::Left, EKey::ArrowLeft),
+            (Key::Right, EKey::ArrowRight),
+            (Key::Up, EKey::ArrowUp),
+            (Key::Escape, EKey::Escape),
+            (Key::Tab, EKey::Tab),
+            (Key::Backspace, EKey::Backspace),
+            (Key::Enter, EKey::Enter),
+            (Key::Space, EKey::Space),
+            (Key::Insert, EKey::Insert),
+            (Key::Delete, EKey::Delete),
+            (Key::Home, EKey::Home),
+            (Key::End, EKey::End),
+            (Key::PageUp, EKey::PageUp),
+            (Key::PageDown, EKey::PageDown),
+            (Key::LBracket, EKey::OpenBracket),
+            (Key::RBracket, EKey::CloseBracket),
+            (Key::Num0, EKey::Num0),
+            (Key::Num1, EKey::Num1),
+            (Key::Num2, EKey::Num2),
+            (Key::Num3, EKey::Num3),
+            (Key::Num4, EKey::Num4),
+            (Key::Num5, EKey::Num5),
+            (Key::Num6, EKey::Num6),
+            (Key::Num7, EKey::Num7),
+            (Key::Num8, EKey::Num8),
+            (Key::Num9, EKey::Num9),
+            (Key::A, EKey::A),
+            (Key::B, EKey::B),
+            (Key::C, EKey::C),
+            (Key::D, EKey::D),
+            (Key::E, EKey::E),
+            (Key::F, EKey::F),
+            (Key::G, EKey::G),
+            (Key::H, EKey::H),
+            (Key::I, EKey::I),
+            (Key::J, EKey::J),
+            (Key::K, EKey::K),
+            (Key::L, EKey::L),
+            (Key::M, EKey::M),
+            (Key::N, EKey::N),
+            (Key::O, EKey::O),
+            (Key::P, EKey::P),
+            (Key::Q, EKey::Q),
+            (Key::R, EKey::R),
+            (Key::S, EKey::S),
+            (Key::T, EKey::T),
+            (Key::U, EKey::U),
+            (Key::V, EKey::V),
+            (Key::W, EKey::W),
+            (Key::X, EKey::X),
+            (Key::Y, EKey::Y),
+            (Key::Z, EKey::Z),
+            (Key::F1, EKey::F1),
+            (Key::F2, EKey::F2),
+            (Key::F3, EKey::F3),
+            (Key::F4, EKey::F4),
+            (Key::F5, EKey::F5),
+            (Key::F6, EKey::F6),
+            (Key::F7, EKey::F7),
+            (Key::F8, EKey::F8),
+            (Key::F9, EKey::F9),
+            (Key::F10, EKey::F10),
+            (Key::F11, EKey::F11),
+            (Key::F12, EKey::F12),
+            (Key::Equal, EKey::Equals),
+            (Key::Hyphen, EKey::Minus),
+            (Key::Slash, EKey::Slash),
+            (Key::Tilde, EKey::Backtick),
+        ];
+        for (sf_key, egui_key) in mapped {
+            assert_eq!(key_conv(sf_key), Some(egui_key), "{sf_key:?}");
+        }
+        for unmapped in [
+            Key::LShift,
+            Key::RControl,
+            Key::Menu,
+            Key::Numpad5,
+            Key::Unknown,
+        ] {
+            assert_eq!(key_conv(unmapped), None, "{unmapped:?}");
+        }
+    }
+
+    #[test]
+    fn button_conv_maps_primary_buttons() {
+        assert_eq!(
+            button_conv(mouse::Button::Left),
+            Some(PointerButton::Primary)
+        );
+        assert_eq!(
+            button_conv(mouse::Button::Right),
+            Some(PointerButton::Secondary)
+        );
+        assert_eq!(
+            button_conv(mouse::Button::Middle),
+            Some(PointerButton::Middle)
+        );
+        assert_eq!(button_conv(mouse::Button::XButton1), None);
+        assert_eq!(button_conv(mouse::Button::XButton2), None);
+    }
+
+    #[test]
+    fn modifier_follows_command_key() {
+        let ctrl = modifier(false, true, false, false, CommandKey::Ctrl);
+        assert!(ctrl.ctrl && ctrl.command && !ctrl.mac_cmd);
+        let system = modifier(false, false, false, true, CommandKey::Ctrl);
+        assert!(!system.command && !system.mac_cmd);
+
+        let system = modifier(false, false, false, true, CommandKey::System);
+        assert!(system.command && system.mac_cmd && !system.ctrl);
+        let ctrl = modifier(false, true, false, false, CommandKey::System);
+        assert!(ctrl.ctrl && !ctrl.command && !ctrl.mac_cmd);
+
+        let others = modifier(true, false, true, false, CommandKey::Ctrl);
+        assert_eq!(
+            others,
+            Modifiers {
+                alt: true,
+                shift: true,
+                ..Modifiers::NONE
+            }
+        );
+    }
+
+    #[test]
+    fn clipboard_shortcuts() {
+        for (command_key, mods) in [(CommandKey::Ctrl, CTRL), (CommandKey::System, SYSTEM)] {
+            let mut state = new_state(command_key);
+            let shortcuts = [
+                (Key::C, egui::Event::Copy),
+                (Key::X, egui::Event::Cut),
+                (Key::V, egui::Event::Paste(String::new())),
+            ];
+            for (code, expected) in shortcuts {
+                let events = translate(&mut state, key_event(code, true, mods));
+                assert_eq!(events.first(), Some(&expected), "{command_key:?} {code:?}");
+                let events = translate(&mut state, key_event(code, false, mods));
+                assert!(
+                    !events.contains(&expected),
+                    "{command_key:?} {code:?} released"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clipboard_keys_without_command() {
+        for (command_key, mods) in [
+            (CommandKey::Ctrl, NO_MODS),
+            (CommandKey::Ctrl, SYSTEM),
+            (CommandKey::System, CTRL),
+        ] {
+            let mut state = new_state(command_key);
+            for code in [Key::C, Key::X, Key::V] {
+                let events = translate(&mut state, key_event(code, true, mods));
+                assert!(
+                    events
+                        .iter()
+                        .all(|ev| matches!(ev, egui::Event::Key { .. })),
+                    "{command_key:?} {code:?}: {events:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn text_entered_drops_control_characters() {
+        let state = new_state(CommandKey::Ctrl);
+        let text = translate_event(&Event::TextEntered { unicode: 'é' }, &state);
+        assert_eq!(text.as_slice(), &[egui::Event::Text("é".into())]);
+        for unicode in ['\u{8}', '\r', '\t', '\u{7f}', '\u{3}'] {
+            assert!(translate_event(&Event::TextEntered { unicode }, &state).is_empty());
+        }
+    }
+
+    #[test]
+    fn scrolling_zooms_only_with_command() {
+        let scroll = |delta| Event::MouseWheelScrolled {
+            wheel: mouse::Wheel::VerticalWheel,
+            delta,
+            x: 0,
+            y: 0,
+        };
+        let mut state = new_state(CommandKey::Ctrl);
+        assert!(translate(&mut state, scroll(1.0)).is_empty());
+        translate(&mut state, key_event(Key::LControl, true, CTRL));
+        assert_eq!(translate(&mut state, scroll(1.0)), [egui::Event::Zoom(1.1)]);
+        assert_eq!(
+            translate(&mut state, scroll(-1.0)),
+            [egui::Event::Zoom(0.9)]
+        );
+        translate(&mut state, key_event(Key::LControl, false, NO_MODS));
+        assert!(translate(&mut state, scroll(1.0)).is_empty());
+    }
+
+    #[test]
+    fn update_tracks_modifiers() {
+        let mut state = new_state(CommandKey::Ctrl);
+        state.update(&key_event(Key::LShift, true, [false, false, true, false]));
+        assert!(state.modifiers.shift);
+        state.update(&key_event(Key::A, true, [false, false, true, false]));
+        assert!(state.modifiers.shift);
+        state.update(&key_event(Key::LShift, false, NO_MODS));
+        assert_eq!(state.modifiers, Modifiers::NONE);
+    }
+
+    #[test]
+    fn update_trusts_key_code_over_flags() {
+        let mut state = new_state(CommandKey::Ctrl);
+        // The flags of the event still report the state before the press
+        state.update(&key_event(Key::RControl, true, NO_MODS));
+        assert!(state.modifiers.ctrl && state.modifiers.command);
+        // ... and before the release
+        state.update(&key_event(Key::RControl, false, CTRL));
+        assert_eq!(state.modifiers, Modifiers::NONE);
+
+        let mut state = new_state(CommandKey::System);
+        state.update(&key_event(Key::LSystem, true, NO_MODS));
+        assert!(state.modifiers.command && state.modifiers.mac_cmd);
+        state.update(&key_event(Key::LAlt, true, SYSTEM));
+        assert!(state.modifiers.alt && state.modifiers.command);
+    }
+
+    #[test]
+    fn lost_focus_clears_modifiers() {
+        let mut state = new_state(CommandKey::Ctrl);
+        state.update(&key_event(Key::LControl, true, CTRL));
+        state.update(&key_event(Key::LAlt, true, [true, true, false, false]));
+        assert!(state.modifiers.alt && state.modifiers.ctrl);
+        state.update(&Event::LostFocus);
+        assert_eq!(state.modifiers, Modifiers::NONE);
+    }
+}
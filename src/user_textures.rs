@@ -0,0 +1,144 @@
+use {
+    crate::SfEgui,
+    egui::{Rect, TextureHandle, TextureId, Vec2},
+    sfml::{
+        cpp::FBox,
+        graphics::{
+            blend_mode::{Equation, Factor},
+            BlendMode, IntRect, Sprite, Texture,
+        },
+    },
+    std::cell::Ref,
+};
+
+/// The blend mode to draw egui textures with.
+///
+/// egui textures hold colors premultiplied by alpha, so drawing them with the default
+/// [`BlendMode::ALPHA`] makes translucent parts too dark.
+pub const PREMULTIPLIED_ALPHA: BlendMode = BlendMode {
+    color_src_factor: Factor::One,
+    color_dst_factor: Factor::OneMinusSrcAlpha,
+    color_equation: Equation::Add,
+    alpha_src_factor: Factor::One,
+    alpha_dst_factor: Factor::OneMinusSrcAlpha,
+    alpha_equation: Equation::Add,
+};
+
+/// Create a sprite showing the part of `texture` covered by `uv`.
+///
+/// `uv` is in normalized texture coordinates, like the UVs of [`egui::Image`].
+pub fn uv_sprite(texture: &Texture, uv: Rect) -> Sprite<'_> {
+    let size = texture.size();
+    let (w, h) = (size.x as f32, size.y as f32);
+    let rect = IntRect::new(
+        (uv.min.x * w).round() as i32,
+        (uv.min.y * h).round() as i32,
+        (uv.width() * w).round() as i32,
+        (uv.height() * h).round() as i32,
+    );
+    Sprite::with_texture_and_rect(texture, rect)
+}
+
+/// The first id handed out by [`SfEgui::register_texture`].
+///
+/// Ids below this are left to [`UserTexSource`](crate::UserTexSource) implementations.
+pub const FIRST_REGISTERED_TEXTURE_ID: u64 = 1 << 63;
+
+/// A texture that can be given to [`SfEgui::register_texture`]
+pub trait IntoUserTexture {
+    /// Get an owned texture
+    fn into_texture(self) -> FBox<Texture>;
+}
+
+impl IntoUserTexture for FBox<Texture> {
+    fn into_texture(self) -> FBox<Texture> {
+        self
+    }
+}
+
+/// Makes a copy of the texture
+impl IntoUserTexture for &Texture {
+    fn into_texture(self) -> FBox<Texture> {
+        self.to_owned()
+    }
+}
+
+impl SfEgui {
+    /// Make `texture` available to egui, and get the id to show it with.
+    ///
+    /// `draw` resolves registered textures itself, no [`UserTexSource`](crate::UserTexSource)
+    /// needed. They are also available in child viewports.
+    pub fn register_texture(&mut self, texture: impl IntoUserTexture) -> TextureId {
+        let id = TextureId::User(self.next_user_texture);
+        self.next_user_texture += 1;
+        self.shared
+            .textures
+            .borrow_mut()
+            .insert(id, texture.into_texture());
+        id
+    }
+    /// Replace the texture registered as `id`, and get the old one back.
+    ///
+    /// Returns `None` and doesn't register `texture` if `id` is not registered.
+    pub fn replace_texture(
+        &mut self,
+        id: TextureId,
+        texture: impl IntoUserTexture,
+    ) -> Option<FBox<Texture>> {
+        if !self.is_registered(id) {
+            return None;
+        }
+        self.shared
+            .textures
+            .borrow_mut()
+            .insert(id, texture.into_texture())
+    }
+    /// Stop making the texture registered as `id` available to egui, and get it back.
+    pub fn unregister_texture(&mut self, id: TextureId) -> Option<FBox<Texture>> {
+        if !self.is_registered(id) {
+            return None;
+        }
+        self.shared.textures.borrow_mut().remove(&id)
+    }
+    /// The size of the texture `id` in pixels, for registered and egui managed textures
+    pub fn texture_size(&self, id: TextureId) -> Option<Vec2> {
+        let textures = self.shared.textures.borrow();
+        let size = textures.get(&id)?.size();
+        Some(Vec2::new(size.x as f32, size.y as f32))
+    }
+    /// The texture for `id`, which can be a registered or an egui managed texture.
+    ///
+    /// This gives SFML drawing code access to textures egui loaded, like with
+    /// [`Context::load_texture`](egui::Context::load_texture). Draw them with
+    /// [`PREMULTIPLIED_ALPHA`].
+    ///
+    /// The returned reference borrows the `SfEgui`, so it has to be dropped before the
+    /// next pass, where egui might free or replace the texture.
+    pub fn managed_texture(&self, id: TextureId) -> Option<Ref<'_, Texture>> {
+        Ref::filter_map(self.shared.textures.borrow(), |textures| {
+            textures.get(&id).map(|tex| &**tex)
+        })
+        .ok()
+    }
+    /// Call `f` with a sprite showing the part of the texture of `handle` covered by `uv`.
+    ///
+    /// Returns `None` if the texture hasn't been uploaded yet, which happens
+    /// at the end of the pass it was loaded in. See [`uv_sprite`].
+    pub fn with_sprite<R>(
+        &self,
+        handle: &TextureHandle,
+        uv: Rect,
+        f: impl FnOnce(Sprite<'_>) -> R,
+    ) -> Option<R> {
+        let texture = self.managed_texture(handle.id())?;
+        Some(f(uv_sprite(&texture, uv)))
+    }
+    /// Whether `id` was returned by [`Self::register_texture`], and is still registered.
+    ///
+    /// [`SfmlViewport`](crate::SfmlViewport) ids come from the same range, but never have
+    /// a texture in the map.
+    fn is_registered(&self, id: TextureId) -> bool {
+        matches!(id, TextureId::User(n) if n >= FIRST_REGISTERED_TEXTURE_ID)
+            && self.shared.textures.borrow().contains_key(&id)
+    }
+}
@@ -0,0 +1,684 @@
+use {
+    crate::{rendering, translate::TranslationState, PassError, Shared, Window},
+    egui::{
+        Context, CursorGrab, DeferredViewportUiCallback, IconData, ImmediateViewport, Pos2,
+        RawInput, Rect, Vec2, ViewportBuilder, ViewportCommand, ViewportEvent, ViewportId,
+        ViewportIdMap, ViewportInfo, ViewportOutput,
+    },
+    sfml::{
+        cpp::FBox,
+        graphics::{Color, FloatRect, RenderTarget as _, RenderWindow, View},
+        system::{Vector2i, Vector2u},
+        window::{ContextSettings, Event, Style, VideoMode},
+    },
+    std::{
+        cell::RefCell,
+        collections::{hash_map::Entry, HashSet},
+        mem::Discriminant,
+        rc::{Rc, Weak},
+        sync::Arc,
+    },
+};
+
+/// Multiplies the rects of `info` by `scale`
+pub(crate) fn rescale_info(info: &mut ViewportInfo, scale: f32) {
+    if scale == 1.0 {
+        return;
+    }
+    for rect in [&mut info.inner_rect, &mut info.outer_rect]
+        .into_iter()
+        .flatten()
+    {
+        *rect = *rect * scale;
+    }
+    if let Some(size) = &mut info.monitor_size {
+        *size *= scale;
+    }
+}
+
+/// Window attributes that SFML can't query, but that we need to keep track of
+/// in order to carry out [`ViewportCommand`]s.
+pub(crate) struct WindowState {
+    title: String,
+    resizable: bool,
+    decorations: bool,
+    close_button: bool,
+    fullscreen: bool,
+    min_inner_size: Option<Vec2>,
+    max_inner_size: Option<Vec2>,
+    pub(crate) cursor_visible: bool,
+    cursor_grabbed: bool,
+    icon: Option<Arc<IconData>>,
+    /// Position of the window before it was hidden, `None` while it's not hidden
+    last_window_pos: Option<Vector2i>,
+    /// Whether the window was maximized with [`ViewportCommand::Maximized`]
+    maximized: bool,
+    /// Size and position of the window before it went fullscreen or was maximized
+    windowed_geometry: Option<(Vector2u, Vector2i)>,
+    /// The kinds of commands that were reported as unsupported, so they're only reported once
+    reported_commands: HashSet<Discriminant<ViewportCommand>>,
+    /// What egui gets to know about the window through [`egui::InputState::viewport`]
+    pub(crate) info: ViewportInfo,
+    /// The pixels per point the rects in `info` are in
+    info_pixels_per_point: f32,
+    /// Whether the last pass was asked to close the window, and didn't cancel it
+    pub(crate) close_requested: bool,
+}
+
+impl Default for WindowState {
+    fn default() -> Self {
+        let mut state = Self {
+            title: String::new(),
+            resizable: false,
+            decorations: false,
+            close_button: false,
+            fullscreen: false,
+            min_inner_size: None,
+            max_inner_size: None,
+            cursor_visible: true,
+            cursor_grabbed: false,
+            icon: None,
+            last_window_pos: None,
+            maximized: false,
+            windowed_geometry: None,
+            reported_commands: HashSet::new(),
+            info: ViewportInfo {
+                native_pixels_per_point: Some(1.0),
+                ..Default::default()
+            },
+            info_pixels_per_point: 1.0,
+            close_requested: false,
+        };
+        state.set_attributes("", Style::DEFAULT);
+        state
+    }
+}
+
+impl WindowState {
+    pub(crate) fn set_attributes(&mut self, title: &str, style: Style) {
+        title.clone_into(&mut self.title);
+        self.resizable = style.contains(Style::RESIZE);
+        self.decorations = style.contains(Style::TITLEBAR);
+        self.close_button = style.contains(Style::CLOSE);
+        self.fullscreen = style.contains(Style::FULLSCREEN);
+    }
+    /// Updates the viewport info from the current state of `rw`.
+    ///
+    /// egui wants the rects in points, so they are converted with the pixels per point
+    /// of the next pass.
+    pub(crate) fn update_info(&mut self, rw: &dyn Window, pixels_per_point: f32) {
+        let size = rw.size();
+        let pos = rw.position();
+        // SFML can't tell the size of the window decorations, so the outer rect is
+        // approximated with the inner one.
+        let rect = Rect::from_min_size(
+            Pos2::new(pos.x as f32, pos.y as f32),
+            Vec2::new(size.x as f32, size.y as f32),
+        ) / pixels_per_point;
+        let desktop = rw.desktop_size();
+        self.info_pixels_per_point = pixels_per_point;
+        let info = &mut self.info;
+        info.inner_rect = Some(rect);
+        info.outer_rect = Some(rect);
+        info.monitor_size = Some(Vec2::new(desktop.x as f32, desktop.y as f32) / pixels_per_point);
+        info.focused = Some(rw.has_focus());
+        info.fullscreen = Some(self.fullscreen);
+        info.maximized = Some(self.maximized);
+        info.title = (!self.title.is_empty()).then(|| self.title.clone());
+    }
+    /// The viewport info for a pass with `pixels_per_point`.
+    ///
+    /// The rects are rescaled if the scale changed since they were last updated, for example
+    /// because the zoom factor was set between passes.
+    pub(crate) fn info_for_pass(&self, pixels_per_point: f32) -> ViewportInfo {
+        let mut info = self.info.clone();
+        rescale_info(&mut info, self.info_pixels_per_point / pixels_per_point);
+        info
+    }
+    /// Updates the viewport info from an SFML event
+    pub(crate) fn update_info_from_event(&mut self, event: &Event) {
+        let info = &mut self.info;
+        match *event {
+            Event::Closed => info.events.push(ViewportEvent::Close),
+            Event::GainedFocus => info.focused = Some(true),
+            Event::LostFocus => info.focused = Some(false),
+            Event::Resized { width, height } => {
+                let size = Vec2::new(width as f32, height as f32) / self.info_pixels_per_point;
+                let min = info.inner_rect.map_or(Pos2::ZERO, |r| r.min);
+                info.inner_rect = Some(Rect::from_min_size(min, size));
+                info.outer_rect = info.inner_rect;
+            }
+            _ => {}
+        }
+    }
+    fn style(&self) -> Style {
+        if self.fullscreen {
+            return Style::FULLSCREEN;
+        }
+        if !self.decorations {
+            return Style::NONE;
+        }
+        let mut style = Style::TITLEBAR;
+        if self.resizable {
+            style |= Style::RESIZE;
+        }
+        if self.close_button {
+            style |= Style::CLOSE;
+        }
+        style
+    }
+    /// Clamps a size in pixels to the min/max inner size, which are in points
+    fn clamp_size(&self, size: Vector2u, pixels_per_point: f32) -> Vector2u {
+        let mut new = Vec2::new(size.x as f32, size.y as f32);
+        if let Some(min) = self.min_inner_size {
+            new = new.max(min * pixels_per_point);
+        }
+        if let Some(max) = self.max_inner_size {
+            new = new.min(max * pixels_per_point);
+        }
+        Vector2u::new(new.x as u32, new.y as u32)
+    }
+    /// Enforces the min/max inner size constraints on the window
+    pub(crate) fn enforce_size_limits(&self, rw: &mut dyn Window, pixels_per_point: f32) {
+        if self.fullscreen {
+            return;
+        }
+        let size = rw.size();
+        let clamped = self.clamp_size(size, pixels_per_point);
+        if clamped != size {
+            rw.set_size(clamped);
+        }
+    }
+    /// Recreates the window with the current style, because SFML can't change
+    /// the style of an existing window.
+    ///
+    /// Note that this resets window settings SFML can't query, like vertical sync.
+    fn recreate(&mut self, rw: &mut dyn Window, raw_input: &mut RawInput) {
+        let (size, pos) = if self.fullscreen {
+            if self.windowed_geometry.is_none() {
+                self.windowed_geometry = Some((rw.size(), rw.position()));
+            }
+            (rw.desktop_size(), None)
+        } else {
+            // The geometry from before a maximize is restored too
+            self.maximized = false;
+            match self.windowed_geometry.take() {
+                Some((size, pos)) => (size, Some(pos)),
+                None => (rw.size(), Some(rw.position())),
+            }
+        };
+        rw.recreate(size, &self.title, self.style());
+        if let Some(pos) = pos {
+            rw.set_position(pos);
+        }
+        if let Some(icon) = &self.icon {
+            rw.set_icon(icon);
+        }
+        rw.set_mouse_cursor_grabbed(self.cursor_grabbed);
+        let size = rw.size();
+        raw_input.screen_rect = Some(crate::raw_input_screen_rect(size.x, size.y));
+    }
+    /// Emulates maximizing by covering the desktop, because SFML can't maximize windows
+    fn set_maximized(&mut self, rw: &mut dyn Window, raw_input: &mut RawInput, maximized: bool) {
+        if self.maximized == maximized || self.fullscreen {
+            return;
+        }
+        self.maximized = maximized;
+        if maximized {
+            self.windowed_geometry = Some((rw.size(), rw.position()));
+            rw.set_position(Vector2i::new(0, 0));
+            rw.set_size(rw.desktop_size());
+        } else if let Some((size, pos)) = self.windowed_geometry.take() {
+            rw.set_size(size);
+            rw.set_position(pos);
+        }
+        let size = rw.size();
+        raw_input.screen_rect = Some(crate::raw_input_screen_rect(size.x, size.y));
+    }
+    /// Returns `true` the first time it's called with a command of the kind of `cmd`
+    fn first_report(&mut self, cmd: &ViewportCommand) -> bool {
+        self.reported_commands.insert(std::mem::discriminant(cmd))
+    }
+    /// Carries out `cmd` on `rw`.
+    ///
+    /// Sizes and positions in `cmd` are in points, and converted to pixels with `pixels_per_point`.
+    pub(crate) fn apply_command(
+        &mut self,
+        rw: &mut dyn Window,
+        raw_input: &mut RawInput,
+        cmd: ViewportCommand,
+        pixels_per_point: f32,
+    ) {
+        rw.command_applied(&cmd);
+        match cmd {
+            ViewportCommand::Close => rw.close(),
+            ViewportCommand::CancelClose => self.close_requested = false,
+            ViewportCommand::Title(s) => {
+                rw.set_title(&s);
+                self.title = s;
+            }
+            ViewportCommand::Visible(visible) => {
+                if !visible && self.last_window_pos.is_none() {
+                    self.last_window_pos = Some(rw.position());
+                }
+                rw.set_visible(visible);
+                // Some platforms forget the position of hidden windows
+                if let Some(pos) = self.last_window_pos.filter(|_| visible) {
+                    rw.set_position(pos);
+                    self.last_window_pos = None;
+                }
+            }
+            ViewportCommand::Focus => {
+                // This trick forces focus where `request_focus` would
+                // only flash the tray icon.
+                let rw_pos = rw.position();
+                rw.set_visible(false);
+                rw.set_visible(true);
+                rw.set_position(rw_pos);
+            }
+            ViewportCommand::InnerSize(size) => {
+                let size = size * pixels_per_point;
+                let size = Vector2u::new(size.x.max(1.) as u32, size.y.max(1.) as u32);
+                let size = self.clamp_size(size, pixels_per_point);
+                rw.set_size(size);
+                raw_input.screen_rect = Some(crate::raw_input_screen_rect(size.x, size.y));
+            }
+            ViewportCommand::OuterPosition(pos) => {
+                let pos = pos * pixels_per_point;
+                rw.set_position(Vector2i::new(pos.x as i32, pos.y as i32));
+            }
+            ViewportCommand::MinInnerSize(size) => {
+                self.min_inner_size = Some(size);
+                self.enforce_size_limits(rw, pixels_per_point);
+            }
+            ViewportCommand::MaxInnerSize(size) => {
+                self.max_inner_size = Some(size);
+                self.enforce_size_limits(rw, pixels_per_point);
+            }
+            ViewportCommand::Resizable(resizable) => {
+                if self.resizable != resizable {
+                    self.resizable = resizable;
+                    self.recreate(rw, raw_input);
+                }
+            }
+            ViewportCommand::Decorations(decorations) => {
+                if self.decorations != decorations {
+                    self.decorations = decorations;
+                    self.recreate(rw, raw_input);
+                }
+            }
+            ViewportCommand::EnableButtons { close, .. } => {
+                if self.close_button != close {
+                    self.close_button = close;
+                    self.recreate(rw, raw_input);
+                }
+            }
+            ViewportCommand::Fullscreen(fullscreen) => {
+                if self.fullscreen != fullscreen {
+                    self.fullscreen = fullscreen;
+                    self.recreate(rw, raw_input);
+                }
+            }
+            ViewportCommand::Icon(Some(icon)) => {
+                rw.set_icon(&icon);
+                self.icon = Some(icon);
+            }
+            // SFML can't reset the icon, so it's only forgotten for when the window is recreated
+            ViewportCommand::Icon(None) => self.icon = None,
+            ViewportCommand::CursorVisible(visible) => {
+                // Applied together with the cursor icon at the end of the pass
+                self.cursor_visible = visible;
+            }
+            ViewportCommand::CursorGrab(grab) => {
+                self.cursor_grabbed = grab != CursorGrab::None;
+                rw.set_mouse_cursor_grabbed(self.cursor_grabbed);
+            }
+            ViewportCommand::CursorPosition(pos) => {
+                let pos = pos * pixels_per_point;
+                rw.set_mouse_position(Vector2i::new(pos.x as i32, pos.y as i32));
+            }
+            ViewportCommand::Maximized(maximized) => self.set_maximized(rw, raw_input, maximized),
+            ViewportCommand::Minimized(_) => {
+                if self.first_report(&cmd) {
+                    eprintln!("egui_sfml: {cmd:?} is not supported by SFML");
+                }
+            }
+            _ => {
+                if self.first_report(&cmd) {
+                    eprintln!("egui_sfml: Unhandled ViewportCommand: {cmd:?}");
+                }
+            }
+        }
+    }
+}
+
+/// An egui viewport other than the root, shown in its own SFML window
+pub(crate) struct ChildViewport {
+    window: FBox<RenderWindow>,
+    state: WindowState,
+    builder: ViewportBuilder,
+    raw_input: RawInput,
+    translation: TranslationState,
+    /// The last known screen rect, in pixels
+    screen_rect: Rect,
+    /// `None` for immediate viewports, which are run by egui itself
+    ui_cb: Option<Arc<DeferredViewportUiCallback>>,
+    pending_screenshots: Vec<egui::UserData>,
+}
+
+/// The windows of all child viewports
+pub(crate) type Children = ViewportIdMap<ChildViewport>;
+
+impl ChildViewport {
+    /// Creates the window for `builder`, whose sizes and positions are in the points of `ctx`.
+    ///
+    /// The viewport inherits the native pixels per point of its parent.
+    fn new(builder: ViewportBuilder, ctx: &Context) -> Option<Self> {
+        let pixels_per_point = ctx.pixels_per_point();
+        let title = builder.title.clone().unwrap_or_default();
+        let mut style = Style::NONE;
+        if builder.fullscreen == Some(true) {
+            style = Style::FULLSCREEN;
+        } else if builder.decorations != Some(false) {
+            style |= Style::TITLEBAR;
+            if builder.resizable != Some(false) {
+                style |= Style::RESIZE;
+            }
+            if builder.close_button != Some(false) {
+                style |= Style::CLOSE;
+            }
+        }
+        let size = builder.inner_size.unwrap_or(Vec2::new(640., 480.)) * pixels_per_point;
+        let mode = if style.contains(Style::FULLSCREEN) {
+            VideoMode::desktop_mode()
+        } else {
+            VideoMode::new(size.x.max(1.) as u32, size.y.max(1.) as u32, 32)
+        };
+        let mut window =
+            match RenderWindow::new(mode, title.as_str(), style, &ContextSettings::default()) {
+                Ok(window) => window,
+                Err(e) => {
+                    eprintln!("egui_sfml: Failed to create window for viewport: {e}");
+                    return None;
+                }
+            };
+        let mut state = WindowState::default();
+        state.set_attributes(&title, style);
+        state.info.native_pixels_per_point = ctx.native_pixels_per_point();
+        let mut raw_input = crate::make_raw_input(&*window);
+        // Visibility goes first, so showing the window doesn't undo the position
+        let initial_commands = [
+            builder.visible.map(ViewportCommand::Visible),
+            builder.position.map(ViewportCommand::OuterPosition),
+            builder.min_inner_size.map(ViewportCommand::MinInnerSize),
+            builder.max_inner_size.map(ViewportCommand::MaxInnerSize),
+            builder.maximized.map(ViewportCommand::Maximized),
+            builder
+                .icon
+                .clone()
+                .map(|icon| ViewportCommand::Icon(Some(icon))),
+        ];
+        for cmd in initial_commands.into_iter().flatten() {
+            state.apply_command(&mut *window, &mut raw_input, cmd, pixels_per_point);
+        }
+        state.update_info(&*window, crate::next_pixels_per_point(ctx, &state.info));
+        Some(Self {
+            window,
+            state,
+            builder,
+            raw_input,
+            translation: TranslationState::default(),
+            screen_rect: Rect::ZERO,
+            ui_cb: None,
+            pending_screenshots: Vec::new(),
+        })
+    }
+    /// Brings the window up to date with a new builder from egui
+    ///
+    /// Returns `false` if the window had to be recreated, but that failed.
+    fn update_builder(&mut self, builder: ViewportBuilder, ctx: &Context) -> bool {
+        let (commands, recreate) = self.builder.patch(builder);
+        if recreate {
+            match Self::new(self.builder.clone(), ctx) {
+                Some(new) => *self = new,
+                None => return false,
+            }
+        } else {
+            self.apply_commands(commands, ctx.pixels_per_point());
+        }
+        true
+    }
+    fn apply_commands(&mut self, commands: Vec<ViewportCommand>, pixels_per_point: f32) {
+        for cmd in commands {
+            match cmd {
+                ViewportCommand::Screenshot(user_data) => self.pending_screenshots.push(user_data),
+                cmd => self.state.apply_command(
+                    &mut *self.window,
+                    &mut self.raw_input,
+                    cmd,
+                    pixels_per_point,
+                ),
+            }
+        }
+    }
+    /// Feeds the events of the window to egui, and returns whether there were any
+    fn poll_events(&mut self, shared: &Shared) -> bool {
+        let mut any = false;
+        while let Some(event) = self.window.poll_event() {
+            any = true;
+            crate::handle_event(&mut self.raw_input, &mut self.translation, &event, shared);
+            self.state.update_info_from_event(&event);
+            if let Event::Resized { width, height } = event {
+                let rect = FloatRect::new(0., 0., width as f32, height as f32);
+                if let Ok(view) = View::from_rect(rect) {
+                    self.window.set_view(&view);
+                }
+            }
+        }
+        any
+    }
+    /// Runs an egui pass for this viewport, and draws the result into its window
+    ///
+    /// Returns the viewport output of the pass.
+    fn run_pass(
+        &mut self,
+        ctx: &Context,
+        shared: &Shared,
+        id: ViewportId,
+        time: f64,
+        mut ui: impl FnMut(&Context),
+    ) -> Result<ViewportIdMap<ViewportOutput>, PassError> {
+        self.raw_input.time = Some(time);
+        self.raw_input.viewport_id = id;
+        let ppp = crate::next_pixels_per_point(ctx, &self.state.info);
+        let info = self.state.info_for_pass(ppp);
+        self.state.info.events.clear();
+        self.raw_input.viewports = std::iter::once((id, info)).collect();
+        crate::pixels_to_points(&mut self.raw_input, &mut self.screen_rect, ppp);
+        let out = ctx.run(self.raw_input.take(), |ctx| {
+            crate::apply_pending_zoom(ctx, ppp);
+            ui(ctx);
+        });
+        crate::update_textures(&mut shared.textures.borrow_mut(), &out.textures_delta)?;
+        self.state
+            .enforce_size_limits(&mut *self.window, out.pixels_per_point);
+        let next_ppp = crate::next_pixels_per_point(ctx, &self.state.info);
+        self.state.update_info(&*self.window, next_ppp);
+        Window::set_mouse_cursor(
+            &mut *self.window,
+            crate::shown_cursor(out.platform_output.cursor_icon, self.state.cursor_visible),
+        );
+        crate::run_output_commands(
+            &mut **shared.clipboard.borrow_mut(),
+            out.platform_output.commands,
+        );
+        let clear_color = ctx.style().visuals.panel_fill;
+        let [r, g, b, a] = clear_color.to_array();
+        self.window.clear(Color::rgba(r, g, b, a));
+        rendering::draw(
+            &mut self.window,
+            ctx,
+            out.shapes,
+            None,
+            shared,
+            out.pixels_per_point,
+        );
+        if !self.pending_screenshots.is_empty() {
+            match rendering::capture(&self.window) {
+                Ok(image) => {
+                    let image = Arc::new(image);
+                    for user_data in self.pending_screenshots.drain(..) {
+                        self.raw_input.events.push(egui::Event::Screenshot {
+                            viewport_id: id,
+                            user_data,
+                            image: image.clone(),
+                        });
+                    }
+                }
+                Err(e) => {
+                    eprintln!("egui_sfml: Failed to capture screenshot: {e}");
+                    self.pending_screenshots.clear();
+                }
+            }
+        }
+        self.window.display();
+        Ok(out.viewport_output)
+    }
+}
+
+/// Creates, updates and destroys the windows of child viewports according to
+/// the viewport output of a pass, and carries out their commands.
+///
+/// The output for [`ViewportId::ROOT`] is left to the caller.
+pub(crate) fn sync_children(
+    children: &mut Children,
+    viewport_output: &mut ViewportIdMap<ViewportOutput>,
+    ctx: &Context,
+) {
+    // Sizes and positions in the commands are in the points of the last pass
+    let pixels_per_point = ctx.pixels_per_point();
+    children.retain(|id, _| viewport_output.contains_key(id));
+    for (&id, out) in viewport_output.iter_mut() {
+        if id == ViewportId::ROOT {
+            continue;
+        }
+        let builder = out.builder.clone();
+        let child = match children.entry(id) {
+            Entry::Occupied(mut entry) => {
+                if !entry.get_mut().update_builder(builder, ctx) {
+                    entry.remove();
+                    continue;
+                }
+                entry.into_mut()
+            }
+            Entry::Vacant(entry) => match ChildViewport::new(builder, ctx) {
+                Some(child) => entry.insert(child),
+                None => continue,
+            },
+        };
+        child.state.info.parent = Some(out.parent);
+        child.ui_cb.clone_from(&out.viewport_ui_cb);
+        child.apply_commands(std::mem::take(&mut out.commands), pixels_per_point);
+    }
+}
+
+/// Runs the passes of all deferred child viewports
+///
+/// Returns the viewport outputs of the passes, which have to be synced like the output of the
+/// root pass.
+pub(crate) fn run_deferred(
+    ctx: &Context,
+    shared: &Shared,
+    time: f64,
+) -> Result<Vec<ViewportIdMap<ViewportOutput>>, PassError> {
+    let deferred: Vec<(ViewportId, Arc<DeferredViewportUiCallback>)> = shared
+        .children
+        .borrow()
+        .iter()
+        .filter_map(|(&id, child)| Some((id, child.ui_cb.clone()?)))
+        .collect();
+    let mut outputs = Vec::new();
+    for (id, ui_cb) in deferred {
+        // The child is taken out of the map during its pass, because its ui can
+        // show immediate viewports, which need access to the map.
+        let Some(mut child) = shared.children.borrow_mut().remove(&id) else {
+            continue;
+        };
+        child.poll_events(shared);
+        let result = child.run_pass(ctx, shared, id, time, |ctx| ui_cb(ctx));
+        shared.children.borrow_mut().insert(id, child);
+        outputs.push(result?);
+    }
+    Ok(outputs)
+}
+
+/// Feeds the pending events of all child viewport windows to egui,
+/// and returns whether there were any
+pub(crate) fn poll_children(shared: &Shared) -> bool {
+    let mut children = shared.children.borrow_mut();
+    let mut any = false;
+    for child in children.values_mut() {
+        any |= child.poll_events(shared);
+    }
+    any
+}
+
+thread_local! {
+    /// The state of every multi-viewport `SfEgui` on this thread, by context.
+    ///
+    /// egui has one immediate viewport renderer per thread, so it looks up the
+    /// `SfEgui` of the context it's called with here.
+    static HOSTS: RefCell<Vec<(Context, Weak<Shared>)>> = RefCell::default();
+}
+
+/// Makes egui show the immediate viewports of `ctx` in their own windows
+pub(crate) fn install_immediate_renderer(ctx: &Context, shared: &Rc<Shared>) {
+    HOSTS.with_borrow_mut(|hosts| {
+        hosts.retain(|(host_ctx, host)| host_ctx != ctx && host.strong_count() > 0);
+        hosts.push((ctx.clone(), Rc::downgrade(shared)));
+    });
+    Context::set_immediate_viewport_renderer(render_immediate);
+}
+
+/// Makes egui embed the immediate viewports of `ctx` again
+pub(crate) fn uninstall_immediate_renderer(ctx: &Context) {
+    HOSTS.with_borrow_mut(|hosts| {
+        hosts.retain(|(host_ctx, host)| host_ctx != ctx && host.strong_count() > 0);
+    });
+}
+
+fn render_immediate(ctx: &Context, viewport: ImmediateViewport<'_>) {
+    let ImmediateViewport {
+        ids,
+        builder,
+        mut viewport_ui_cb,
+    } = viewport;
+    let shared = HOSTS.with_borrow(|hosts| {
+        hosts
+            .iter()
+            .find(|(host_ctx, _)| host_ctx == ctx)
+            .and_then(|(_, host)| host.upgrade())
+    });
+    let Some(shared) = shared else {
+        // Not a context of a multi-viewport `SfEgui`, so show the ui embedded
+        viewport_ui_cb(ctx);
+        return;
+    };
+    let existing = shared.children.borrow_mut().remove(&ids.this);
+    let child = match existing {
+        Some(mut child) => child.update_builder(builder, ctx).then_some(child),
+        None => ChildViewport::new(builder, ctx),
+    };
+    let Some(mut child) = child else {
+        // egui expects the ui to be shown no matter what
+        viewport_ui_cb(ctx);
+        return;
+    };
+    child.poll_events(&shared);
+    let time = ctx.input(|inp| inp.time);
+    // Commands of immediate viewports are reported in the output of their parent
+    if let Err(e) = child.run_pass(ctx, &shared, ids.this, time, |ctx| viewport_ui_cb(ctx)) {
+        eprintln!("egui_sfml: Immediate viewport pass failed: {e}");
+    }
+    shared.children.borrow_mut().insert(ids.this, child);
+}
@@ -0,0 +1,218 @@
+use {
+    egui::{CursorIcon, IconData, TexturesDelta, ViewportCommand},
+    sfml::{
+        cpp::FBox,
+        graphics::{RenderTarget, RenderWindow, Texture},
+        system::{Vector2i, Vector2u},
+        window::{Cursor, CursorType, Style, VideoMode},
+    },
+};
+
+/// The window operations [`SfEgui`](crate::SfEgui) needs to carry out egui's output.
+///
+/// Implemented for [`RenderWindow`]. [`MockWindow`](crate::headless::MockWindow) implements it
+/// without a display, for testing.
+pub trait Window {
+    /// Inner size of the window
+    fn size(&self) -> Vector2u;
+    /// Change the inner size of the window
+    fn set_size(&mut self, size: Vector2u);
+    /// Position of the window on the desktop
+    fn position(&self) -> Vector2i;
+    /// Move the window on the desktop
+    fn set_position(&mut self, position: Vector2i);
+    /// Whether the window has input focus
+    fn has_focus(&self) -> bool;
+    /// Change the title of the window
+    fn set_title(&mut self, title: &str);
+    /// Show or hide the window
+    fn set_visible(&mut self, visible: bool);
+    /// Close the window
+    fn close(&mut self);
+    /// Recreate the window with a new size and style
+    fn recreate(&mut self, size: Vector2u, title: &str, style: Style);
+    /// Change the icon of the window
+    fn set_icon(&mut self, icon: &IconData);
+    /// Show the mouse cursor corresponding to `icon`, or hide it if `None`
+    fn set_mouse_cursor(&mut self, icon: Option<CursorIcon>);
+    /// Confine the mouse cursor to the window
+    fn set_mouse_cursor_grabbed(&mut self, grabbed: bool);
+    /// Move the mouse cursor, relative to the window
+    fn set_mouse_position(&mut self, position: Vector2i);
+    /// Size of the desktop the window is on
+    fn desktop_size(&self) -> Vector2u;
+    /// The maximum size of a texture, in pixels
+    fn max_texture_side(&self) -> usize;
+    /// Whether egui textures can be uploaded for this window.
+    ///
+    /// Windows without an OpenGL context should return `false`.
+    fn supports_textures(&self) -> bool {
+        true
+    }
+    /// Called with every [`ViewportCommand`] before it's carried out
+    fn command_applied(&mut self, _cmd: &ViewportCommand) {}
+    /// Called with the texture changes egui outputs at the end of every pass
+    fn textures_updated(&mut self, _delta: &TexturesDelta) {}
+}
+
+impl Window for RenderWindow {
+    fn size(&self) -> Vector2u {
+        RenderTarget::size(self)
+    }
+    fn set_size(&mut self, size: Vector2u) {
+        RenderWindow::set_size(self, size);
+    }
+    fn position(&self) -> Vector2i {
+        RenderWindow::position(self)
+    }
+    fn set_position(&mut self, position: Vector2i) {
+        RenderWindow::set_position(self, position);
+    }
+    fn has_focus(&self) -> bool {
+        RenderWindow::has_focus(self)
+    }
+    fn set_title(&mut self, title: &str) {
+        RenderWindow::set_title(self, title);
+    }
+    fn set_visible(&mut self, visible: bool) {
+        RenderWindow::set_visible(self, visible);
+    }
+    fn close(&mut self) {
+        RenderWindow::close(self);
+    }
+    fn recreate(&mut self, size: Vector2u, title: &str, style: Style) {
+        let settings = *self.settings();
+        let mode = VideoMode::new(size.x, size.y, VideoMode::desktop_mode().bits_per_pixel);
+        RenderWindow::recreate(self, mode, title, style, &settings);
+    }
+    fn set_icon(&mut self, icon: &IconData) {
+        let IconData {
+            rgba,
+            width,
+            height,
+        } = icon;
+        if rgba.len() < (*width as usize * *height as usize * 4) {
+            eprintln!("egui_sfml: Icon data is smaller than {width}x{height}");
+            return;
+        }
+        // SAFETY: We just checked that `rgba` contains enough pixels
+        unsafe {
+            RenderWindow::set_icon(self, *width, *height, rgba);
+        }
+    }
+    fn set_mouse_cursor(&mut self, icon: Option<CursorIcon>) {
+        let Some(icon) = icon else {
+            self.set_mouse_cursor_visible(false);
+            return;
+        };
+        self.set_mouse_cursor_visible(true);
+        CURSORS.with(|cursors| {
+            // SAFETY: The cursors live as long as the thread, and windows can't leave their thread
+            unsafe {
+                RenderWindow::set_mouse_cursor(self, cursors.get(icon));
+            }
+        });
+    }
+    fn set_mouse_cursor_grabbed(&mut self, grabbed: bool) {
+        RenderWindow::set_mouse_cursor_grabbed(self, grabbed);
+    }
+    fn set_mouse_position(&mut self, position: Vector2i) {
+        RenderWindow::set_mouse_position(self, position);
+    }
+    fn desktop_size(&self) -> Vector2u {
+        let desktop = VideoMode::desktop_mode();
+        Vector2u::new(desktop.width, desktop.height)
+    }
+    fn max_texture_side(&self) -> usize {
+        Texture::maximum_size() as usize
+    }
+}
+
+/// So windows created with [`RenderWindow::new`] can be used directly
+impl Window for FBox<RenderWindow> {
+    fn size(&self) -> Vector2u {
+        Window::size(&**self)
+    }
+    fn set_size(&mut self, size: Vector2u) {
+        Window::set_size(&mut **self, size);
+    }
+    fn position(&self) -> Vector2i {
+        Window::position(&**self)
+    }
+    fn set_position(&mut self, position: Vector2i) {
+        Window::set_position(&mut **self, position);
+    }
+    fn has_focus(&self) -> bool {
+        Window::has_focus(&**self)
+    }
+    fn set_title(&mut self, title: &str) {
+        Window::set_title(&mut **self, title);
+    }
+    fn set_visible(&mut self, visible: bool) {
+        Window::set_visible(&mut **self, visible);
+    }
+    fn close(&mut self) {
+        Window::close(&mut **self);
+    }
+    fn recreate(&mut self, size: Vector2u, title: &str, style: Style) {
+        Window::recreate(&mut **self, size, title, style);
+    }
+    fn set_icon(&mut self, icon: &IconData) {
+        Window::set_icon(&mut **self, icon);
+    }
+    fn set_mouse_cursor(&mut self, icon: Option<CursorIcon>) {
+        Window::set_mouse_cursor(&mut **self, icon);
+    }
+    fn set_mouse_cursor_grabbed(&mut self, grabbed: bool) {
+        Window::set_mouse_cursor_grabbed(&mut **self, grabbed);
+    }
+    fn set_mouse_position(&mut self, position: Vector2i) {
+        Window::set_mouse_position(&mut **self, position);
+    }
+    fn desktop_size(&self) -> Vector2u {
+        Window::desktop_size(&**self)
+    }
+    fn max_texture_side(&self) -> usize {
+        Window::max_texture_side(&**self)
+    }
+}
+
+thread_local! {
+    static CURSORS: Cursors = Cursors::default();
+}
+
+struct Cursors {
+    arrow: FBox<Cursor>,
+    horizontal: FBox<Cursor>,
+    vertical: FBox<Cursor>,
+    hand: FBox<Cursor>,
+    cross: FBox<Cursor>,
+    text: FBox<Cursor>,
+}
+
+impl Cursors {
+    /// The cursor corresponding to `icon`
+    fn get(&self, icon: CursorIcon) -> &Cursor {
+        match icon {
+            CursorIcon::PointingHand | CursorIcon::Grab | CursorIcon::Grabbing => &self.hand,
+            CursorIcon::Crosshair => &self.cross,
+            CursorIcon::Text => &self.text,
+            CursorIcon::ResizeHorizontal | CursorIcon::ResizeColumn => &self.horizontal,
+            CursorIcon::ResizeVertical => &self.vertical,
+            _ => &self.arrow,
+        }
+    }
+}
+
+impl Default for Cursors {
+    fn default() -> Self {
+        Self {
+            arrow: Cursor::from_system(CursorType::Arrow).unwrap(),
+            horizontal: Cursor::from_system(CursorType::SizeHorizontal).unwrap(),
+            vertical: Cursor::from_system(CursorType::SizeVertical).unwrap(),
+            hand: Cursor::from_system(CursorType::Hand).unwrap(),
+            cross: Cursor::from_system(CursorType::Cross).unwrap(),
+            text: Cursor::from_system(CursorType::Text).unwrap(),
+        }
+    }
+}